@@ -1,4 +1,7 @@
-use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use criterion::measurement::WallTime;
+use criterion::{criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion};
+use tree::arenaavltree::ArenaAVLTree;
+use tree::arenarbtree::ArenaRBTree;
 use tree::avltree::AVLTree;
 use tree::rbtree::RBTree;
 use tree::tree::Tree;
@@ -12,7 +15,7 @@ fn bench_rbtree(criterion: &mut Criterion) {
             size,
             |bench, &size| {
                 bench.iter(|| {
-                    let mut tree: RBTree = rbtree::RBTree::new();
+                    let mut tree: RBTree<i64> = rbtree::RBTree::new();
                     for index in 1..size {
                         tree.insert(index);
                     }
@@ -20,7 +23,13 @@ fn bench_rbtree(criterion: &mut Criterion) {
             },
         );
 
-        let mut tree: RBTree = rbtree::RBTree::new();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("from_sorted_iter:{size}")),
+            size,
+            |bench, &size| bench.iter(|| RBTree::<i64>::from_sorted_iter(1..size)),
+        );
+
+        let mut tree: RBTree<i64> = rbtree::RBTree::new();
         for index in 1..*size {
             tree.insert(index);
         }
@@ -31,7 +40,7 @@ fn bench_rbtree(criterion: &mut Criterion) {
             |bench, &size| {
                 bench.iter(|| {
                     for index in 0..size / 10 {
-                        tree.contain(index);
+                        tree.contain(&index);
                     }
                 })
             },
@@ -49,7 +58,7 @@ fn bench_avltree(criterion: &mut Criterion) {
             size,
             |bench, &size| {
                 bench.iter(|| {
-                    let mut tree: AVLTree = avltree::AVLTree::new();
+                    let mut tree: AVLTree<i64> = avltree::AVLTree::new();
                     for index in 1..size {
                         tree.insert(index);
                     }
@@ -57,7 +66,13 @@ fn bench_avltree(criterion: &mut Criterion) {
             },
         );
 
-        let mut tree: AVLTree = avltree::AVLTree::new();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("from_sorted_iter:{size}")),
+            size,
+            |bench, &size| bench.iter(|| AVLTree::<i64>::from_sorted_iter(1..size)),
+        );
+
+        let mut tree: AVLTree<i64> = avltree::AVLTree::new();
         for index in 1..*size {
             tree.insert(index);
         }
@@ -68,20 +83,70 @@ fn bench_avltree(criterion: &mut Criterion) {
             |bench, &size| {
                 bench.iter(|| {
                     for index in 0..size / 10 {
-                        tree.contain(index);
+                        tree.contain(&index);
+                    }
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Runs the insert and search benchmarks of one tree type under `backend`, so
+/// the `Rc` and arena backends show up side by side in one group.
+fn bench_backend<T: Tree<i64>>(group: &mut BenchmarkGroup<WallTime>, backend: &str) {
+    for size in [10000, 40000, 70000, 100000, 130000].iter() {
+        group.bench_with_input(
+            BenchmarkId::new(format!("{backend}/insert"), size),
+            size,
+            |bench, &size| {
+                bench.iter(|| {
+                    let mut tree = T::new();
+                    for index in 1..size {
+                        tree.insert(index);
+                    }
+                })
+            },
+        );
+
+        let mut tree = T::new();
+        for index in 1..*size {
+            tree.insert(index);
+        }
+
+        group.bench_with_input(
+            BenchmarkId::new(format!("{backend}/search"), size),
+            size,
+            |bench, &size| {
+                bench.iter(|| {
+                    for index in 0..size / 10 {
+                        tree.contain(&index);
                     }
                 })
             },
         );
     }
+}
+
+fn bench_rbtree_backends(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("rbtree_backends");
+    bench_backend::<RBTree<i64>>(&mut group, "rc");
+    bench_backend::<ArenaRBTree<i64>>(&mut group, "arena");
+    group.finish();
+}
 
+fn bench_avltree_backends(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("avltree_backends");
+    bench_backend::<AVLTree<i64>>(&mut group, "rc");
+    bench_backend::<ArenaAVLTree<i64>>(&mut group, "arena");
     group.finish();
 }
 
 criterion_group! {
     name = benches;
     config = Criterion::default();
-    targets = bench_rbtree, bench_avltree
+    targets = bench_rbtree, bench_avltree, bench_rbtree_backends, bench_avltree_backends
 }
 
 criterion_main!(benches);
@@ -0,0 +1,72 @@
+//! Drives every tree with the same operation sequence and checks each one
+//! against a `BTreeSet` and its own `validate` after every step. A panic or
+//! a divergence aborts the run and libFuzzer saves the input under
+//! `artifacts/tree_ops/`; replay it with
+//! `cargo fuzz run tree_ops artifacts/tree_ops/<file>`.
+#![no_main]
+
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use tree::arenaavltree::ArenaAVLTree;
+use tree::arenarbtree::ArenaRBTree;
+use tree::avltree::AVLTree;
+use tree::invariant::InvariantViolation;
+use tree::rbtree::RBTree;
+use tree::tree::Tree;
+
+/// Keys are a single byte so inserts, deletes and lookups keep colliding.
+#[derive(Arbitrary, Clone, Copy, Debug)]
+enum Op {
+    Insert(u8),
+    Delete(u8),
+    Contain(u8),
+    /// Inserts through `search_path` and `insert_at`, like the map entry API.
+    InsertAt(u8),
+}
+
+fn run<T: Tree<u8>>(name: &str, ops: &[Op], validate: fn(&T) -> Result<(), InvariantViolation<u8>>) {
+    let mut tree = T::new();
+    let mut model = BTreeSet::new();
+
+    for (step, op) in ops.iter().enumerate() {
+        match *op {
+            Op::Insert(key) => {
+                check(name, step, op, tree.insert(key), model.insert(key));
+            }
+            Op::Delete(key) => {
+                check(name, step, op, tree.delete(&key), model.take(&key));
+            }
+            Op::Contain(key) => {
+                check(name, step, op, tree.contain(&key), model.contains(&key));
+            }
+            Op::InsertAt(key) => {
+                let (exists, path) = tree.search_path(&key);
+                if !exists {
+                    tree.insert_at(path, key);
+                }
+                model.insert(key);
+            }
+        }
+        if let Err(violation) = validate(&tree) {
+            panic!("{name}: step {step} ({op:?}) broke an invariant: {violation}");
+        }
+        check(name, step, op, tree.len(), model.len());
+        check(name, step, op, tree.get_min(), model.first().copied());
+        check(name, step, op, tree.get_max(), model.last().copied());
+        check(name, step, op, tree.in_order_traversal(), model.iter().copied().collect());
+    }
+}
+
+fn check<V: PartialEq + Debug>(name: &str, step: usize, op: &Op, actual: V, expected: V) {
+    assert_eq!(actual, expected, "{name}: diverged from BTreeSet at step {step} ({op:?})");
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    run::<RBTree<u8>>("RBTree", &ops, RBTree::validate);
+    run::<AVLTree<u8>>("AVLTree", &ops, AVLTree::validate);
+    run::<ArenaRBTree<u8>>("ArenaRBTree", &ops, ArenaRBTree::validate);
+    run::<ArenaAVLTree<u8>>("ArenaAVLTree", &ops, ArenaAVLTree::validate);
+});
@@ -0,0 +1,132 @@
+use std::ops::{Index, IndexMut};
+
+/// Index of a node inside an `Arena`.
+pub type NodeId = u32;
+
+enum Slot<T> {
+    Occupied(T),
+    Vacant { next_free: Option<NodeId> },
+}
+
+/// `Vec`-backed node storage. Freed slots are chained into a free list
+/// through the vacant entries and handed out again by `alloc`.
+pub struct Arena<T> {
+    slots: Vec<Slot<T>>,
+    free_head: Option<NodeId>,
+    len: usize,
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Self {
+        Arena {
+            slots: Vec::new(),
+            free_head: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Slots handed out so far, occupied or on the free list.
+    pub fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn alloc(&mut self, value: T) -> NodeId {
+        self.len += 1;
+        match self.free_head {
+            Some(id) => {
+                if let Slot::Vacant { next_free } = self.slots[id as usize] {
+                    self.free_head = next_free;
+                }
+                self.slots[id as usize] = Slot::Occupied(value);
+                id
+            }
+            None => {
+                self.slots.push(Slot::Occupied(value));
+                (self.slots.len() - 1) as NodeId
+            }
+        }
+    }
+
+    /// Removes the value at `id` and puts its slot on the free list.
+    pub fn free(&mut self, id: NodeId) -> T {
+        let vacant = Slot::Vacant {
+            next_free: self.free_head,
+        };
+        let Slot::Occupied(value) = std::mem::replace(&mut self.slots[id as usize], vacant) else {
+            panic!("node {} freed twice", id)
+        };
+        self.free_head = Some(id);
+        self.len -= 1;
+        value
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&T> {
+        match self.slots.get(id as usize) {
+            Some(Slot::Occupied(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut T> {
+        match self.slots.get_mut(id as usize) {
+            Some(Slot::Occupied(value)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<NodeId> for Arena<T> {
+    type Output = T;
+
+    fn index(&self, id: NodeId) -> &T {
+        self.get(id).unwrap_or_else(|| panic!("node {} is not allocated", id))
+    }
+}
+
+impl<T> IndexMut<NodeId> for Arena<T> {
+    fn index_mut(&mut self, id: NodeId) -> &mut T {
+        self.get_mut(id).unwrap_or_else(|| panic!("node {} is not allocated", id))
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::arena::Arena;
+
+    #[test]
+    fn test_arena_reuses_freed_slots() {
+        let mut arena = Arena::new();
+        let first = arena.alloc("a");
+        let second = arena.alloc("b");
+        let third = arena.alloc("c");
+        assert_eq!(arena.len(), 3);
+
+        assert_eq!(arena.free(second), "b");
+        assert_eq!(arena.free(first), "a");
+        assert_eq!(arena.get(first), None);
+        assert_eq!(arena.len(), 1);
+
+        // Most recently freed slot first.
+        assert_eq!(arena.alloc("d"), first);
+        assert_eq!(arena.alloc("e"), second);
+        assert_eq!(arena.alloc("f"), 3);
+        assert_eq!(arena[third], "c");
+        assert_eq!(arena[second], "e");
+        assert_eq!(arena.len(), 4);
+    }
+}
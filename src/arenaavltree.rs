@@ -0,0 +1,376 @@
+use std::cmp::{max, Ordering};
+use std::fmt::{self, Debug, Display};
+
+use crate::arena::{Arena, NodeId};
+use crate::dot::{balance_style, DotNodeStyle};
+use crate::invariant::{validate_balance, validate_shape, InvariantViolation};
+use crate::iter::Iter;
+use crate::render::RenderStyle;
+use crate::tree::Tree;
+
+pub struct ArenaAVLNode<K> {
+    pub key: K,
+    pub left: Option<NodeId>,
+    pub right: Option<NodeId>,
+    pub height: u32,
+    pub size: usize,
+    pub leaves: u32,
+}
+
+/// AVL tree whose nodes live in an `Arena` and link to each other by index.
+pub struct ArenaAVLTree<K> {
+    nodes: Arena<ArenaAVLNode<K>>,
+    _root: Option<NodeId>,
+}
+
+impl<K: Ord + Clone + Debug> Tree<K> for ArenaAVLTree<K> {
+    type NodeRef = NodeId;
+
+    fn new() -> Self {
+        ArenaAVLTree {
+            nodes: Arena::new(),
+            _root: None,
+        }
+    }
+
+    fn get_root(&self) -> Option<NodeId> {
+        self._root
+    }
+
+    fn get_left(&self, node: &NodeId) -> Option<NodeId> {
+        self.nodes[*node].left
+    }
+
+    fn get_right(&self, node: &NodeId) -> Option<NodeId> {
+        self.nodes[*node].right
+    }
+
+    fn with_key<R>(&self, node: &NodeId, f: impl FnOnce(&K) -> R) -> R {
+        f(&self.nodes[*node].key)
+    }
+
+    fn get_node_size(&self, node: &NodeId) -> usize {
+        self.nodes[*node].size
+    }
+
+    fn get_node_height(&self, node: &NodeId) -> u32 {
+        self.nodes[*node].height
+    }
+
+    fn count_node_leaves(&self, node: &NodeId) -> u32 {
+        self.nodes[*node].leaves
+    }
+
+    fn insert(&mut self, key: K) -> bool {
+        let len = self.len();
+        let root = self._insert(self._root, key);
+        self._root = Some(root);
+        self.len() > len
+    }
+
+    fn delete<Q>(&mut self, key: &Q) -> Option<K>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (root, removed) = self._delete(self._root, key);
+        self._root = root;
+        removed
+    }
+
+    fn insert_at(&mut self, mut path: Vec<NodeId>, key: K) {
+        let mut child = self._alloc(key);
+        while let Some(parent) = path.pop() {
+            if self.nodes[child].key < self.nodes[parent].key {
+                self.nodes[parent].left = Some(child);
+            } else {
+                self.nodes[parent].right = Some(child);
+            }
+            child = self._rebalance(parent);
+        }
+        self._root = Some(child);
+    }
+
+    fn delete_at(&mut self, mut path: Vec<NodeId>) -> K {
+        let node = path.pop().unwrap();
+        let (left, right) = (self.nodes[node].left, self.nodes[node].right);
+        let removed = self.nodes.free(node).key;
+        let mut child = match (left, right) {
+            (Some(left), Some(right)) => {
+                // Splice the smallest node of the right subtree in its place.
+                let (right, successor) = self._remove_min(right);
+                self.nodes[successor].left = Some(left);
+                self.nodes[successor].right = right;
+                Some(self._rebalance(successor))
+            }
+            _ => left.or(right),
+        };
+        let mut old_child = node;
+        while let Some(parent) = path.pop() {
+            if self.nodes[parent].left == Some(old_child) {
+                self.nodes[parent].left = child;
+            } else {
+                self.nodes[parent].right = child;
+            }
+            old_child = parent;
+            child = Some(self._rebalance(parent));
+        }
+        self._root = child;
+        removed
+    }
+
+    fn render_sideways(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        match self._root {
+            Some(root) => self._render_node(out, root, "", "Root", false),
+            None => Ok(()),
+        }
+    }
+
+    fn dot_node_style(&self, node: &NodeId) -> DotNodeStyle {
+        balance_style(self, node)
+    }
+}
+
+impl<K: Ord + Clone + Debug> ArenaAVLTree<K> {
+    /// Checks key order, the cached fields and that every node is balanced.
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>> {
+        validate_shape(self)?;
+        validate_balance(self)
+    }
+
+    fn _alloc(&mut self, key: K) -> NodeId {
+        self.nodes.alloc(ArenaAVLNode {
+            key,
+            left: None,
+            right: None,
+            height: 1,
+            size: 1,
+            leaves: 1,
+        })
+    }
+
+    fn _insert(&mut self, node: Option<NodeId>, key: K) -> NodeId {
+        let Some(node) = node else {
+            return self._alloc(key);
+        };
+        match key.cmp(&self.nodes[node].key) {
+            Ordering::Less => {
+                let left = self._insert(self.nodes[node].left, key);
+                self.nodes[node].left = Some(left);
+            }
+            Ordering::Greater => {
+                let right = self._insert(self.nodes[node].right, key);
+                self.nodes[node].right = Some(right);
+            }
+            Ordering::Equal => return node,
+        }
+        self._rebalance(node)
+    }
+
+    /// Returns the new subtree root and the removed key, if any.
+    fn _delete<Q>(&mut self, node: Option<NodeId>, key: &Q) -> (Option<NodeId>, Option<K>)
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let Some(node) = node else {
+            return (None, None);
+        };
+        let (left, right) = (self.nodes[node].left, self.nodes[node].right);
+        let removed = match self.nodes[node].key.borrow().cmp(key) {
+            Ordering::Greater => {
+                let (left, removed) = self._delete(left, key);
+                self.nodes[node].left = left;
+                removed
+            }
+            Ordering::Less => {
+                let (right, removed) = self._delete(right, key);
+                self.nodes[node].right = right;
+                removed
+            }
+            Ordering::Equal => {
+                let removed = Some(self.nodes.free(node).key);
+                let (Some(left), Some(right)) = (left, right) else {
+                    return (left.or(right), removed);
+                };
+                // Splice the smallest node of the right subtree in its place.
+                let (right, successor) = self._remove_min(right);
+                self.nodes[successor].left = Some(left);
+                self.nodes[successor].right = right;
+                return (Some(self._rebalance(successor)), removed);
+            }
+        };
+        (Some(self._rebalance(node)), removed)
+    }
+
+    /// Unlinks the smallest node below `node`. Returns the new subtree root
+    /// and the unlinked node.
+    fn _remove_min(&mut self, node: NodeId) -> (Option<NodeId>, NodeId) {
+        match self.nodes[node].left {
+            None => (self.nodes[node].right, node),
+            Some(left) => {
+                let (left, min) = self._remove_min(left);
+                self.nodes[node].left = left;
+                (Some(self._rebalance(node)), min)
+            }
+        }
+    }
+
+    fn _rebalance(&mut self, node: NodeId) -> NodeId {
+        self._update(node);
+        let (left, right) = (self.nodes[node].left, self.nodes[node].right);
+        let balance_factor = self._height(left) as i64 - self._height(right) as i64;
+
+        if balance_factor > 1 {
+            let left = left.unwrap();
+            if self._height(self.nodes[left].left) < self._height(self.nodes[left].right) {
+                self.nodes[node].left = Some(self._left_rotate(left));
+            }
+            self._right_rotate(node)
+        } else if balance_factor < -1 {
+            let right = right.unwrap();
+            if self._height(self.nodes[right].right) < self._height(self.nodes[right].left) {
+                self.nodes[node].right = Some(self._right_rotate(right));
+            }
+            self._left_rotate(node)
+        } else {
+            node
+        }
+    }
+
+    fn _left_rotate(&mut self, node: NodeId) -> NodeId {
+        let new_root = self.nodes[node].right.unwrap();
+        self.nodes[node].right = self.nodes[new_root].left;
+        self._update(node);
+        self.nodes[new_root].left = Some(node);
+        self._update(new_root);
+        new_root
+    }
+
+    fn _right_rotate(&mut self, node: NodeId) -> NodeId {
+        let new_root = self.nodes[node].left.unwrap();
+        self.nodes[node].left = self.nodes[new_root].right;
+        self._update(node);
+        self.nodes[new_root].right = Some(node);
+        self._update(new_root);
+        new_root
+    }
+
+    fn _height(&self, node: Option<NodeId>) -> u32 {
+        node.map_or(0, |node| self.nodes[node].height)
+    }
+
+    /// Recomputes the cached height, subtree size and leaf count of `node`
+    /// from its children.
+    fn _update(&mut self, node: NodeId) {
+        let (left, right) = (self.nodes[node].left, self.nodes[node].right);
+        let field = |child: Option<NodeId>| {
+            child.map_or((0, 0, 0), |child| {
+                let child = &self.nodes[child];
+                (child.size, child.height, child.leaves)
+            })
+        };
+        let (left_size, left_height, left_leaves) = field(left);
+        let (right_size, right_height, right_leaves) = field(right);
+
+        let this_node = &mut self.nodes[node];
+        this_node.size = 1 + left_size + right_size;
+        this_node.height = 1 + max(left_height, right_height);
+        this_node.leaves = max(left_leaves + right_leaves, 1);
+    }
+
+    fn _render_node(&self, out: &mut dyn fmt::Write, node: NodeId, prefix_space: &str, child_prefix: &str, is_right: bool) -> fmt::Result {
+        let root_prefix = String::from("    ");
+        let prefix_space = if child_prefix == "Root" {
+            writeln!(out)?;
+            &root_prefix
+        } else {
+            prefix_space
+        };
+        let this_node = &self.nodes[node];
+
+        if let Some(left) = this_node.left {
+            let mut new_prefix_space_left = String::from(prefix_space);
+            new_prefix_space_left.push_str(if is_right { "|      " } else { "       " });
+            self._render_node(out, left, &new_prefix_space_left, "L", false)?;
+        }
+        if this_node.left.is_none() && this_node.right.is_none() {
+            writeln!(out, "{}{} {:?}", prefix_space, child_prefix, this_node.key)?;
+        } else {
+            writeln!(out, "{}{} {:?}----|", prefix_space, child_prefix, this_node.key)?;
+        }
+        if let Some(right) = this_node.right {
+            let mut new_prefix_space_right = String::from(prefix_space);
+            if is_right || child_prefix == "Root" {
+                new_prefix_space_right.push_str("       ");
+            } else {
+                new_prefix_space_right.push_str("|      ");
+            }
+            self._render_node(out, right, &new_prefix_space_right, "R", true)?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone + Debug> Display for ArenaAVLTree<K> {
+    /// Draws the tree in `RenderStyle::Unicode`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render(f, RenderStyle::Unicode)
+    }
+}
+
+impl<'a, K: Ord + Clone + Debug> IntoIterator for &'a ArenaAVLTree<K> {
+    type Item = K;
+    type IntoIter = Iter<'a, K, ArenaAVLTree<K>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::arenaavltree::ArenaAVLTree;
+    use crate::tree::Tree;
+
+    #[test]
+    fn test_arena_avltree() {
+        let mut avl_tree: ArenaAVLTree<i64> = ArenaAVLTree::new();
+        let input = vec![9, 5, 15, 11, 19, 10, 14, 20, 13, 12, 18, 25, 6, 16, 4, 3, 2, 1, 7, 8];
+        let to_delete = vec![9, 20, 13, 6, 11, 15, 1, 2, 3, 25, 14];
+        for number in &input {
+            avl_tree.insert(*number);
+            avl_tree.validate().unwrap();
+        }
+        let mut sorted_input = input.clone();
+        sorted_input.sort();
+        assert_eq!(avl_tree.in_order_traversal(), sorted_input);
+        assert_eq!(avl_tree.len(), input.len());
+        assert_eq!(avl_tree.get_height(), 5);
+
+        for number in &to_delete {
+            avl_tree.delete(number);
+            avl_tree.validate().unwrap();
+            assert!(!avl_tree.contain(number));
+        }
+        let remaining: Vec<i64> = sorted_input.into_iter().filter(|n| !to_delete.contains(n)).collect();
+        assert_eq!(avl_tree.in_order_traversal(), remaining);
+        assert_eq!(avl_tree.len(), remaining.len());
+        assert_eq!(avl_tree.get_height(), 4);
+        assert_eq!(avl_tree.count_leaves(), 5);
+
+        for number in &to_delete {
+            avl_tree.insert(*number);
+            avl_tree.validate().unwrap();
+        }
+        assert_eq!(avl_tree.nodes.len(), input.len());
+        assert_eq!(avl_tree.nodes.slot_count(), input.len());
+        for number in &input {
+            avl_tree.delete(number);
+            avl_tree.validate().unwrap();
+        }
+        assert!(avl_tree.is_empty());
+        assert!(avl_tree.nodes.is_empty());
+    }
+}
@@ -0,0 +1,490 @@
+use std::cmp::max;
+use std::fmt::{self, Debug, Display};
+
+use crate::arena::{Arena, NodeId};
+use crate::dot::{color_style, DotNodeStyle};
+use crate::invariant::{validate_shape, InvariantViolation, Rule};
+use crate::iter::Iter;
+use crate::rbnode::NodeColor;
+use crate::render::{color_label, RenderStyle};
+use crate::tree::Tree;
+
+pub struct ArenaRBNode<K> {
+    pub key: K,
+    pub color: NodeColor,
+    pub parent: Option<NodeId>,
+    pub left: Option<NodeId>,
+    pub right: Option<NodeId>,
+    pub size: usize,
+    pub height: u32,
+    pub leaves: u32,
+}
+
+/// Red-black tree whose nodes live in an `Arena` and link to each other by
+/// index, so lookups need no refcounting or `RefCell` borrows.
+pub struct ArenaRBTree<K> {
+    nodes: Arena<ArenaRBNode<K>>,
+    _root: Option<NodeId>,
+}
+
+impl<K: Ord + Clone + Debug> Tree<K> for ArenaRBTree<K> {
+    type NodeRef = NodeId;
+
+    fn new() -> Self {
+        ArenaRBTree {
+            nodes: Arena::new(),
+            _root: None,
+        }
+    }
+
+    fn get_root(&self) -> Option<NodeId> {
+        self._root
+    }
+
+    fn get_left(&self, node: &NodeId) -> Option<NodeId> {
+        self.nodes[*node].left
+    }
+
+    fn get_right(&self, node: &NodeId) -> Option<NodeId> {
+        self.nodes[*node].right
+    }
+
+    fn with_key<R>(&self, node: &NodeId, f: impl FnOnce(&K) -> R) -> R {
+        f(&self.nodes[*node].key)
+    }
+
+    fn get_node_size(&self, node: &NodeId) -> usize {
+        self.nodes[*node].size
+    }
+
+    fn get_node_height(&self, node: &NodeId) -> u32 {
+        self.nodes[*node].height
+    }
+
+    fn count_node_leaves(&self, node: &NodeId) -> u32 {
+        self.nodes[*node].leaves
+    }
+
+    fn insert(&mut self, key: K) -> bool {
+        let (exists, parent) = self.search(&key);
+        if !exists {
+            self._attach(parent, key);
+        }
+        !exists
+    }
+
+    fn delete<Q>(&mut self, key: &Q) -> Option<K>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match self.search(key) {
+            (true, Some(node)) => Some(self._delete_node(node)),
+            _ => None,
+        }
+    }
+
+    fn insert_at(&mut self, mut path: Vec<NodeId>, key: K) {
+        self._attach(path.pop(), key);
+    }
+
+    fn delete_at(&mut self, path: Vec<NodeId>) -> K {
+        self._delete_node(*path.last().unwrap())
+    }
+
+    fn render_sideways(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        match self._root {
+            Some(root) => self._render_node(out, root, "", "Root", false),
+            None => Ok(()),
+        }
+    }
+
+    fn dot_node_style(&self, node: &NodeId) -> DotNodeStyle {
+        color_style(&self.nodes[*node].color)
+    }
+
+    fn render_label(&self, node: &NodeId) -> String {
+        color_label(&self.nodes[*node].key, &self.nodes[*node].color)
+    }
+}
+
+impl<K: Ord + Clone + Debug> ArenaRBTree<K> {
+    /// Checks the red-black rules, the `parent` links and every cached field.
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>> {
+        validate_shape(self)?;
+        if let Some(root) = self._root {
+            if self.nodes[root].color == NodeColor::Red {
+                return Err(InvariantViolation::new(self.nodes[root].key.clone(), Rule::RedRoot));
+            }
+            self._validate_node(root, None)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of black nodes below `node` down to a NIL leaf,
+    /// counting the leaf but not `node`.
+    fn _validate_node(&self, node: NodeId, parent: Option<NodeId>) -> Result<u32, InvariantViolation<K>> {
+        let this_node = &self.nodes[node];
+        let violation = |rule| Err(InvariantViolation::new(this_node.key.clone(), rule));
+
+        if this_node.parent != parent {
+            return violation(Rule::ParentLink);
+        }
+        if this_node.color == NodeColor::Red && self._color(parent) == NodeColor::Red {
+            return violation(Rule::RedRedEdge);
+        }
+
+        let mut black_heights = [1, 1];
+        for (child, black_height) in [this_node.left, this_node.right].into_iter().zip(&mut black_heights) {
+            if let Some(child) = child {
+                let is_black = self.nodes[child].color == NodeColor::Black;
+                *black_height = self._validate_node(child, Some(node))? + is_black as u32;
+            }
+        }
+        if black_heights[0] != black_heights[1] {
+            return violation(Rule::BlackHeight);
+        }
+        Ok(black_heights[0])
+    }
+
+    fn _attach(&mut self, parent: Option<NodeId>, key: K) {
+        let is_left = parent.map(|parent| key < self.nodes[parent].key);
+        let node = self.nodes.alloc(ArenaRBNode {
+            key,
+            color: NodeColor::Red,
+            parent,
+            left: None,
+            right: None,
+            size: 1,
+            height: 1,
+            leaves: 1,
+        });
+        match (parent, is_left) {
+            (Some(parent), Some(true)) => self.nodes[parent].left = Some(node),
+            (Some(parent), _) => self.nodes[parent].right = Some(node),
+            (None, _) => self._root = Some(node),
+        }
+        self._insert_repair(node);
+        self._update_upward(Some(node));
+    }
+
+    fn _insert_repair(&mut self, mut node: NodeId) {
+        while let Some(parent) = self.nodes[node].parent {
+            if self.nodes[parent].color == NodeColor::Black {
+                break;
+            }
+            // A red parent is never the root, so the grandparent exists.
+            let grandparent = self.nodes[parent].parent.unwrap();
+            let parent_is_left = self.nodes[grandparent].left == Some(parent);
+            let uncle = if parent_is_left {
+                self.nodes[grandparent].right
+            } else {
+                self.nodes[grandparent].left
+            };
+
+            if self._color(uncle) == NodeColor::Red {
+                self.nodes[parent].color = NodeColor::Black;
+                self.nodes[uncle.unwrap()].color = NodeColor::Black;
+                self.nodes[grandparent].color = NodeColor::Red;
+                node = grandparent;
+                continue;
+            }
+
+            let mut top = parent;
+            if parent_is_left {
+                if self.nodes[parent].right == Some(node) {
+                    self._left_rotate(parent);
+                    top = node;
+                }
+                self._right_rotate(grandparent);
+            } else {
+                if self.nodes[parent].left == Some(node) {
+                    self._right_rotate(parent);
+                    top = node;
+                }
+                self._left_rotate(grandparent);
+            }
+            self.nodes[top].color = NodeColor::Black;
+            self.nodes[grandparent].color = NodeColor::Red;
+            break;
+        }
+        let root = self._root.unwrap();
+        self.nodes[root].color = NodeColor::Black;
+    }
+
+    fn _delete_node(&mut self, node: NodeId) -> K {
+        let (removed_color, child, child_parent) = match (self.nodes[node].left, self.nodes[node].right) {
+            (Some(left), Some(right)) => {
+                let mut successor = right;
+                while let Some(next) = self.nodes[successor].left {
+                    successor = next;
+                }
+                let removed_color = self.nodes[successor].color.clone();
+                let child = self.nodes[successor].right;
+
+                let child_parent = if self.nodes[successor].parent == Some(node) {
+                    Some(successor)
+                } else {
+                    let child_parent = self.nodes[successor].parent;
+                    self._transplant(successor, child);
+                    self.nodes[successor].right = Some(right);
+                    self.nodes[right].parent = Some(successor);
+                    child_parent
+                };
+                self._transplant(node, Some(successor));
+                self.nodes[successor].left = Some(left);
+                self.nodes[left].parent = Some(successor);
+                self.nodes[successor].color = self.nodes[node].color.clone();
+                (removed_color, child, child_parent)
+            }
+            (left, right) => {
+                let child = left.or(right);
+                let child_parent = self.nodes[node].parent;
+                self._transplant(node, child);
+                (self.nodes[node].color.clone(), child, child_parent)
+            }
+        };
+
+        if removed_color == NodeColor::Black {
+            self._delete_repair(child, child_parent);
+        }
+        // Repair rotations stay on the path above `child_parent`.
+        self._update_upward(child_parent);
+        self.nodes.free(node).key
+    }
+
+    /// Restores the red-black properties after a black node was removed
+    /// above `node`. `parent` is tracked separately because `node` may be NIL.
+    fn _delete_repair(&mut self, mut node: Option<NodeId>, mut parent: Option<NodeId>) {
+        while node != self._root && self._color(node) == NodeColor::Black {
+            let Some(this_parent) = parent else { break };
+
+            if self.nodes[this_parent].left == node {
+                // The removed black node leaves the sibling side at least
+                // one black node deeper, so the sibling exists.
+                let mut sibling = self.nodes[this_parent].right.unwrap();
+                if self.nodes[sibling].color == NodeColor::Red {
+                    self.nodes[sibling].color = NodeColor::Black;
+                    self.nodes[this_parent].color = NodeColor::Red;
+                    self._left_rotate(this_parent);
+                    sibling = self.nodes[this_parent].right.unwrap();
+                }
+                let (near, far) = (self.nodes[sibling].left, self.nodes[sibling].right);
+                if self._color(near) == NodeColor::Black && self._color(far) == NodeColor::Black {
+                    self.nodes[sibling].color = NodeColor::Red;
+                    node = Some(this_parent);
+                    parent = self.nodes[this_parent].parent;
+                    continue;
+                }
+                if self._color(far) == NodeColor::Black {
+                    self.nodes[near.unwrap()].color = NodeColor::Black;
+                    self.nodes[sibling].color = NodeColor::Red;
+                    self._right_rotate(sibling);
+                    sibling = self.nodes[this_parent].right.unwrap();
+                }
+                self.nodes[sibling].color = self.nodes[this_parent].color.clone();
+                self.nodes[this_parent].color = NodeColor::Black;
+                let far = self.nodes[sibling].right.unwrap();
+                self.nodes[far].color = NodeColor::Black;
+                self._left_rotate(this_parent);
+            } else {
+                let mut sibling = self.nodes[this_parent].left.unwrap();
+                if self.nodes[sibling].color == NodeColor::Red {
+                    self.nodes[sibling].color = NodeColor::Black;
+                    self.nodes[this_parent].color = NodeColor::Red;
+                    self._right_rotate(this_parent);
+                    sibling = self.nodes[this_parent].left.unwrap();
+                }
+                let (near, far) = (self.nodes[sibling].right, self.nodes[sibling].left);
+                if self._color(near) == NodeColor::Black && self._color(far) == NodeColor::Black {
+                    self.nodes[sibling].color = NodeColor::Red;
+                    node = Some(this_parent);
+                    parent = self.nodes[this_parent].parent;
+                    continue;
+                }
+                if self._color(far) == NodeColor::Black {
+                    self.nodes[near.unwrap()].color = NodeColor::Black;
+                    self.nodes[sibling].color = NodeColor::Red;
+                    self._left_rotate(sibling);
+                    sibling = self.nodes[this_parent].left.unwrap();
+                }
+                self.nodes[sibling].color = self.nodes[this_parent].color.clone();
+                self.nodes[this_parent].color = NodeColor::Black;
+                let far = self.nodes[sibling].left.unwrap();
+                self.nodes[far].color = NodeColor::Black;
+                self._right_rotate(this_parent);
+            }
+            node = self._root;
+        }
+        if let Some(node) = node {
+            self.nodes[node].color = NodeColor::Black;
+        }
+    }
+
+    /// Puts `new` where `old` hangs below its parent.
+    fn _transplant(&mut self, old: NodeId, new: Option<NodeId>) {
+        let parent = self.nodes[old].parent;
+        self._replace_child(parent, old, new);
+        if let Some(new) = new {
+            self.nodes[new].parent = parent;
+        }
+    }
+
+    fn _replace_child(&mut self, parent: Option<NodeId>, old: NodeId, new: Option<NodeId>) {
+        match parent {
+            None => self._root = new,
+            Some(parent) if self.nodes[parent].left == Some(old) => self.nodes[parent].left = new,
+            Some(parent) => self.nodes[parent].right = new,
+        }
+    }
+
+    fn _left_rotate(&mut self, node: NodeId) {
+        let right = self.nodes[node].right.unwrap();
+        let inner = self.nodes[right].left;
+        self.nodes[node].right = inner;
+        if let Some(inner) = inner {
+            self.nodes[inner].parent = Some(node);
+        }
+        self._transplant(node, Some(right));
+        self.nodes[right].left = Some(node);
+        self.nodes[node].parent = Some(right);
+        self._update(node);
+        self._update(right);
+    }
+
+    fn _right_rotate(&mut self, node: NodeId) {
+        let left = self.nodes[node].left.unwrap();
+        let inner = self.nodes[left].right;
+        self.nodes[node].left = inner;
+        if let Some(inner) = inner {
+            self.nodes[inner].parent = Some(node);
+        }
+        self._transplant(node, Some(left));
+        self.nodes[left].right = Some(node);
+        self.nodes[node].parent = Some(left);
+        self._update(node);
+        self._update(left);
+    }
+
+    fn _color(&self, node: Option<NodeId>) -> NodeColor {
+        node.map_or(NodeColor::Black, |node| self.nodes[node].color.clone())
+    }
+
+    /// Recomputes the cached size, height and leaf count of `node` from its
+    /// children.
+    fn _update(&mut self, node: NodeId) {
+        let (left, right) = (self.nodes[node].left, self.nodes[node].right);
+        let field = |child: Option<NodeId>| {
+            child.map_or((0, 0, 0), |child| {
+                let child = &self.nodes[child];
+                (child.size, child.height, child.leaves)
+            })
+        };
+        let (left_size, left_height, left_leaves) = field(left);
+        let (right_size, right_height, right_leaves) = field(right);
+
+        let this_node = &mut self.nodes[node];
+        this_node.size = 1 + left_size + right_size;
+        this_node.height = 1 + max(left_height, right_height);
+        this_node.leaves = max(left_leaves + right_leaves, 1);
+    }
+
+    fn _update_upward(&mut self, mut node: Option<NodeId>) {
+        while let Some(this_node) = node {
+            self._update(this_node);
+            node = self.nodes[this_node].parent;
+        }
+    }
+
+    fn _render_node(&self, out: &mut dyn fmt::Write, node: NodeId, prefix_space: &str, child_prefix: &str, is_right: bool) -> fmt::Result {
+        if child_prefix == "Root" {
+            writeln!(out)?;
+        }
+        let this_node = &self.nodes[node];
+        let color = if this_node.color == NodeColor::Black { "Black" } else { "Red" };
+
+        if let Some(left) = this_node.left {
+            let mut new_prefix_space_left = String::from(prefix_space);
+            new_prefix_space_left.push_str(if is_right { "|     " } else { "      " });
+            self._render_node(out, left, &new_prefix_space_left, "L", false)?;
+        }
+        writeln!(out, "{}{} {:?} {}", prefix_space, child_prefix, this_node.key, color)?;
+        if let Some(right) = this_node.right {
+            let mut new_prefix_space_right = String::from(prefix_space);
+            if is_right || child_prefix == "Root" {
+                new_prefix_space_right.push_str("      ");
+            } else {
+                new_prefix_space_right.push_str("|     ");
+            }
+            self._render_node(out, right, &new_prefix_space_right, "R", true)?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone + Debug> Display for ArenaRBTree<K> {
+    /// Draws the tree in `RenderStyle::Unicode`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render(f, RenderStyle::Unicode)
+    }
+}
+
+impl<'a, K: Ord + Clone + Debug> IntoIterator for &'a ArenaRBTree<K> {
+    type Item = K;
+    type IntoIter = Iter<'a, K, ArenaRBTree<K>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::arenarbtree::ArenaRBTree;
+    use crate::tree::Tree;
+
+    #[test]
+    fn test_arena_rbtree() {
+        let mut rb_tree: ArenaRBTree<i64> = ArenaRBTree::new();
+        let input = vec![9, 5, 15, 11, 19, 10, 14, 20, 13, 12, 18, 25, 6, 16, 4, 3, 2, 1, 7, 8];
+        let to_delete = vec![9, 20, 13, 6, 11, 15, 1, 2, 3, 25, 14];
+        for number in &input {
+            rb_tree.insert(*number);
+            rb_tree.validate().unwrap();
+        }
+        let mut sorted_input = input.clone();
+        sorted_input.sort();
+        assert_eq!(rb_tree.in_order_traversal(), sorted_input);
+        assert_eq!(rb_tree.len(), input.len());
+        assert_eq!(rb_tree.get_height(), 5);
+        assert_eq!(rb_tree.count_leaves(), 9);
+        // Insertion balances exactly like the Rc-backed tree.
+        assert_eq!(rb_tree.pre_order_traversal(), vec![11, 5, 3, 2, 1, 4, 9, 7, 6, 8, 10, 15, 13, 12, 14, 19, 18, 16, 20, 25]);
+
+        for number in &to_delete {
+            rb_tree.delete(number);
+            rb_tree.validate().unwrap();
+            assert!(!rb_tree.contain(number));
+        }
+        let remaining: Vec<i64> = sorted_input.into_iter().filter(|n| !to_delete.contains(n)).collect();
+        assert_eq!(rb_tree.in_order_traversal(), remaining);
+        assert_eq!(rb_tree.len(), remaining.len());
+        assert_eq!(rb_tree.get_height(), 4);
+        assert_eq!(rb_tree.count_leaves(), 5);
+
+        // Deleted slots are reused instead of growing the arena.
+        for number in &to_delete {
+            rb_tree.insert(*number);
+            rb_tree.validate().unwrap();
+        }
+        assert_eq!(rb_tree.nodes.len(), input.len());
+        assert_eq!(rb_tree.nodes.slot_count(), input.len());
+        for number in &input {
+            rb_tree.delete(number);
+            rb_tree.validate().unwrap();
+        }
+        assert!(rb_tree.is_empty());
+        assert!(rb_tree.nodes.is_empty());
+    }
+}
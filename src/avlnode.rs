@@ -2,47 +2,79 @@ use crate::node::Node;
 use std::cell::RefCell;
 use std::cmp::max;
 use std::cmp::Ordering;
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 use std::rc::Rc;
 
-type RcRefcellAVLNode = Rc<RefCell<AVLNode>>;
-pub type OptionNode = Option<RcRefcellAVLNode>;
+pub type RcRefcellAVLNode<K> = Rc<RefCell<AVLNode<K>>>;
+pub type OptionNode<K> = Option<RcRefcellAVLNode<K>>;
 
 #[derive(Debug)]
-pub struct AVLNode {
-    pub key: i64,
-    left: OptionNode,
-    right: OptionNode,
+pub struct AVLNode<K> {
+    pub key: K,
+    left: OptionNode<K>,
+    right: OptionNode<K>,
     height: u32,
+    size: usize,
+    count: usize,
+    total: usize,
+    leaves: u32,
 }
 
-impl Node for AVLNode {
-    fn new(key: i64) -> OptionNode {
+impl<K: Ord + Clone + Debug> Node<K> for AVLNode<K> {
+    fn new(key: K) -> OptionNode<K> {
         Some(Rc::new(RefCell::new(AVLNode {
             key,
             left: None,
             right: None,
             height: 1,
+            size: 1,
+            count: 1,
+            total: 1,
+            leaves: 1,
         })))
     }
 
-    fn get_left(&self) -> &OptionNode {
+    fn get_left(&self) -> &OptionNode<K> {
         &self.left
     }
 
-    fn get_right(&self) -> &OptionNode {
+    fn get_right(&self) -> &OptionNode<K> {
         &self.right
     }
 
-    fn get_key(&self) -> i64 {
-        self.key
+    fn get_key(&self) -> &K {
+        &self.key
     }
 
-    fn print_node(&self, prefix_space: &String, child_prefix: String, is_right: bool) {
+    fn get_size(&self) -> usize {
+        self.size
+    }
+
+    fn get_count(&self) -> usize {
+        self.count
+    }
+
+    fn get_total(&self) -> usize {
+        self.total
+    }
+
+    fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    fn count_leaves(&self) -> u32 {
+        self.leaves
+    }
+
+    fn count_nodes(&self) -> u32 {
+        self.size as u32
+    }
+
+    fn render_node(&self, out: &mut dyn fmt::Write, prefix_space: &str, child_prefix: String, is_right: bool) -> fmt::Result {
         let mut new_prefix_space_right: String;
         let mut new_prefix_space_left: String;
         if child_prefix == "Root" {
-            print!("\n");
+            writeln!(out)?;
             new_prefix_space_right = String::from("    ");
             new_prefix_space_left = String::from("    ");
         } else {
@@ -57,12 +89,12 @@ impl Node for AVLNode {
             }
 
             left.borrow()
-                .print_node(&new_prefix_space_left, "L".to_string(), false);
+                .render_node(out, &new_prefix_space_left, "L".to_string(), false)?;
         }
         if self.get_left().is_none() && self.get_right().is_none() {
-            println!("{}{} {:?}", prefix_space, child_prefix, self.key);
+            writeln!(out, "{}{} {:?}", prefix_space, child_prefix, self.key)?;
         } else {
-            println!("{}{} {:?}----|", prefix_space, child_prefix, self.key);
+            writeln!(out, "{}{} {:?}----|", prefix_space, child_prefix, self.key)?;
         }
         if let Some(right) = self.get_right() {
             match is_right {
@@ -78,82 +110,108 @@ impl Node for AVLNode {
 
             right
                 .borrow()
-                .print_node(&new_prefix_space_right, "R".to_string(), true);
+                .render_node(out, &new_prefix_space_right, "R".to_string(), true)?;
         }
+        Ok(())
     }
 }
 
-impl AVLNode {
-    fn _get_height(node: Option<RcRefcellAVLNode>) -> u32 {
+impl<K: Ord + Clone + Debug> AVLNode<K> {
+    fn _get_height(node: OptionNode<K>) -> u32 {
         node.map_or(0, |this_node| this_node.borrow().height)
     }
 
-    fn _get_left_height(node: &RcRefcellAVLNode) -> u32 {
+    fn _get_left_height(node: &RcRefcellAVLNode<K>) -> u32 {
         Self::_get_height(node.borrow().left.clone())
     }
 
-    fn _get_right_height(node: &RcRefcellAVLNode) -> u32 {
+    fn _get_right_height(node: &RcRefcellAVLNode<K>) -> u32 {
         Self::_get_height(node.borrow().right.clone())
     }
 
-    fn _get_balance_factor(node: &RcRefcellAVLNode) -> i64 {
+    fn _get_balance_factor(node: &RcRefcellAVLNode<K>) -> i64 {
         Self::_get_left_height(node) as i64 - Self::_get_right_height(node) as i64
     }
 
-    fn _left_rotate(root: RcRefcellAVLNode) -> RcRefcellAVLNode {
+    fn _left_rotate(root: RcRefcellAVLNode<K>) -> RcRefcellAVLNode<K> {
         let new_root = root.borrow().right.clone().unwrap();
-        root.borrow_mut().right = new_root.borrow().left.clone().take();
-        root.borrow_mut().height = 1 + Self::_max_height(&root);
+        root.borrow_mut().right = new_root.borrow().left.clone();
+        Self::_update(&root);
 
         new_root.borrow_mut().left = Some(root);
-        new_root.borrow_mut().height = 1 + Self::_max_height(&new_root);
+        Self::_update(&new_root);
 
         new_root
     }
 
-    fn _max_height(node: &RcRefcellAVLNode) -> u32 {
+    /// Recomputes the cached height, subtree size, total count and leaf
+    /// count of `node` from its children.
+    fn _update(node: &RcRefcellAVLNode<K>) {
+        let height = 1 + Self::_max_height(node);
+        let size = 1 + Self::_get_size(&node.borrow().left) + Self::_get_size(&node.borrow().right);
+        let total = node.borrow().count + Self::_get_total(&node.borrow().left) + Self::_get_total(&node.borrow().right);
+        let leaves = Self::_get_leaves(&node.borrow().left) + Self::_get_leaves(&node.borrow().right);
+        let mut this_node = node.borrow_mut();
+        this_node.height = height;
+        this_node.size = size;
+        this_node.total = total;
+        this_node.leaves = max(leaves, 1);
+    }
+
+    fn _get_size(node: &OptionNode<K>) -> usize {
+        node.as_ref().map_or(0, |this_node| this_node.borrow().size)
+    }
+
+    fn _get_total(node: &OptionNode<K>) -> usize {
+        node.as_ref().map_or(0, |this_node| this_node.borrow().total)
+    }
+
+    fn _get_leaves(node: &OptionNode<K>) -> u32 {
+        node.as_ref().map_or(0, |this_node| this_node.borrow().leaves)
+    }
+
+    fn _max_height(node: &RcRefcellAVLNode<K>) -> u32 {
         max(
-            Self::_get_left_height(&node),
-            Self::_get_right_height(&node),
+            Self::_get_left_height(node),
+            Self::_get_right_height(node),
         )
     }
 
-    fn _right_rotate(root: RcRefcellAVLNode) -> RcRefcellAVLNode {
+    fn _right_rotate(root: RcRefcellAVLNode<K>) -> RcRefcellAVLNode<K> {
         let new_root = root.borrow().left.clone().unwrap();
-        root.borrow_mut().left = new_root.borrow().right.clone().take();
-
-        root.borrow_mut().height = 1 + Self::_max_height(&root);
+        root.borrow_mut().left = new_root.borrow().right.clone();
+        Self::_update(&root);
 
         new_root.borrow_mut().right = Some(root);
-        new_root.borrow_mut().height = 1 + Self::_max_height(&new_root);
+        Self::_update(&new_root);
 
         new_root
     }
 
-    fn _left_right_rotate(root: RcRefcellAVLNode) -> RcRefcellAVLNode {
-        let left = root.borrow().left.clone().take().unwrap();
+    fn _left_right_rotate(root: RcRefcellAVLNode<K>) -> RcRefcellAVLNode<K> {
+        let left = root.borrow().left.clone().unwrap();
         root.borrow_mut().left = Some(Self::_left_rotate(left));
         Self::_right_rotate(root)
     }
 
-    fn _right_left_rotate(root: RcRefcellAVLNode) -> RcRefcellAVLNode {
-        let right = root.borrow().right.clone().take().unwrap();
+    fn _right_left_rotate(root: RcRefcellAVLNode<K>) -> RcRefcellAVLNode<K> {
+        let right = root.borrow().right.clone().unwrap();
         root.borrow_mut().right = Some(Self::_right_rotate(right));
         Self::_left_rotate(root)
     }
 
-    pub fn insert(node: OptionNode, key: i64) -> OptionNode {
+    pub fn insert(node: OptionNode<K>, key: K) -> OptionNode<K> {
         let return_node = match node {
             None => AVLNode::new(key).unwrap(),
             Some(this_node) => {
-                let node_key = this_node.borrow().key;
-                match key.cmp(&node_key) {
+                let ordering = key.cmp(&this_node.borrow().key);
+                match ordering {
                     Ordering::Less => {
-                        let left: OptionNode = this_node.borrow().left.clone();
+                        let left: OptionNode<K> = this_node.borrow().left.clone();
                         this_node.borrow_mut().left = Self::insert(left, key);
                     }
                     Ordering::Greater => {
-                        let right: OptionNode = this_node.borrow().right.clone();
+                        let right: OptionNode<K> = this_node.borrow().right.clone();
                         this_node.borrow_mut().right = Self::insert(right, key);
                     }
                     Ordering::Equal => {}
@@ -162,88 +220,363 @@ impl AVLNode {
             }
         };
         let balance_factor = Self::_get_balance_factor(&return_node);
-        let new_return_node: RcRefcellAVLNode = match balance_factor {
+        let new_return_node: RcRefcellAVLNode<K> = match balance_factor {
             2 => {
-                let new_key = return_node.borrow().left.clone().unwrap().borrow().key;
-                match key.cmp(&new_key) {
-                    Ordering::Less => Self::_right_rotate(return_node),
-                    Ordering::Greater => Self::_left_right_rotate(return_node),
+                let left_child = return_node.borrow().left.clone().unwrap();
+                match Self::_get_balance_factor(&left_child) {
+                    1 => Self::_right_rotate(return_node),
+                    -1 => Self::_left_right_rotate(return_node),
                     _ => return_node,
                 }
             }
             -2 => {
-                let new_key = return_node.borrow().right.clone().unwrap().borrow().key;
-                match key.cmp(&new_key) {
-                    Ordering::Less => Self::_right_left_rotate(return_node),
-                    Ordering::Greater => Self::_left_rotate(return_node),
+                let right_child = return_node.borrow().right.clone().unwrap();
+                match Self::_get_balance_factor(&right_child) {
+                    1 => Self::_right_left_rotate(return_node),
+                    -1 => Self::_left_rotate(return_node),
                     _ => return_node,
                 }
             }
             _ => return_node,
         };
-        new_return_node.borrow_mut().height = max(
-            Self::_get_left_height(&new_return_node),
-            Self::_get_right_height(&new_return_node),
-        ) + 1;
+        Self::_update(&new_return_node);
         Some(new_return_node)
     }
 
-    pub fn delete(node: OptionNode, key: i64) -> OptionNode {
+    /// Returns the new subtree root and the removed key, if any.
+    pub fn delete<Q>(node: OptionNode<K>, key: &Q) -> (OptionNode<K>, Option<K>)
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
         if node.is_none() {
-            return node;
+            return (node, None);
         }
         let this_node = node.unwrap();
-        let return_node: OptionNode;
-        let node_key = this_node.borrow().key;
-        return_node = match node_key.cmp(&key) {
+        let ordering = this_node.borrow().key.borrow().cmp(key);
+        let (return_node, removed): (OptionNode<K>, Option<K>) = match ordering {
             Ordering::Greater => {
-                let left: OptionNode = this_node.borrow().left.clone();
+                let left: OptionNode<K> = this_node.borrow().left.clone();
                 match left {
-                    None => return Some(this_node),
+                    None => return (Some(this_node), None),
                     Some(_) => {
-                        let left: OptionNode = this_node.borrow().left.clone().take();
-                        this_node.borrow_mut().left = Self::delete(left, key);
+                        let (left, removed) = Self::delete(left, key);
+                        this_node.borrow_mut().left = left;
+                        (Some(this_node), removed)
                     }
                 }
-                Some(this_node)
             }
             Ordering::Less => {
-                let right: OptionNode = this_node.borrow().right.clone();
+                let right: OptionNode<K> = this_node.borrow().right.clone();
                 if right.is_none() {
-                    return Some(this_node);
+                    return (Some(this_node), None);
                 }
 
-                let right: OptionNode = this_node.borrow().right.clone().take();
-                this_node.borrow_mut().right = Self::delete(right, key);
+                let (right, removed) = Self::delete(right, key);
+                this_node.borrow_mut().right = right;
     
-                Some(this_node)
+                (Some(this_node), removed)
             }
             Ordering::Equal => {
-                let left: OptionNode = this_node.borrow().left.clone();
-                let right: OptionNode = this_node.borrow().right.clone();
-                if right.is_none() && left.is_none() {
-                    return None;
-                } else if right.is_none() {
-                    let inner_left = left.clone().unwrap();
-                    return Some(inner_left)
-                } else if left.is_none() {
-                    let inner_right = right.clone().unwrap();
-                    return Some(inner_right)
-                } else {
-                    let inner_right = right.clone().unwrap();
-                    let min_value = inner_right.borrow().get_min();
-                    this_node.borrow_mut().key = min_value;
-                    let right = this_node.borrow().right.clone().take();
-                    this_node.borrow_mut().right = Self::delete(right, min_value);
-                    return Some(this_node)
+                let left: OptionNode<K> = this_node.borrow().left.clone();
+                let right: OptionNode<K> = this_node.borrow().right.clone();
+                let (Some(_), Some(inner_right)) = (&left, &right) else {
+                    let removed = Some(this_node.borrow().key.clone());
+                    return (left.or(right), removed);
+                };
+                let min_count = Self::_min_node(inner_right.clone()).borrow().count;
+                let min_value = inner_right.borrow().get_min();
+                let (right, _) = Self::delete(Some(inner_right.clone()), min_value.borrow());
+                let mut this_mut = this_node.borrow_mut();
+                this_mut.right = right;
+                this_mut.count = min_count;
+                let removed = std::mem::replace(&mut this_mut.key, min_value);
+                drop(this_mut);
+                (Some(this_node), Some(removed))
+            }
+        };
+
+        match return_node {
+            None => (None, removed),
+            Some(return_node) => (Some(Self::_rebalance(return_node)), removed),
+        }
+    }
+
+    fn _min_node(node: RcRefcellAVLNode<K>) -> RcRefcellAVLNode<K> {
+        let left = node.borrow().left.clone();
+        left.map_or(node, Self::_min_node)
+    }
+
+    /// Adds `delta` to the count of the last node of `path` (root first) and
+    /// to the totals of every node on it.
+    pub(crate) fn add_count(path: &[RcRefcellAVLNode<K>], delta: isize) {
+        if let Some(last) = path.last() {
+            let mut last = last.borrow_mut();
+            last.count = last.count.checked_add_signed(delta).unwrap();
+        }
+        for node in path {
+            let mut node = node.borrow_mut();
+            node.total = node.total.checked_add_signed(delta).unwrap();
+        }
+    }
+
+    /// Inserts `key` as a child of the last node of `path` (root first) and
+    /// rebalances every node on the way back up. Returns the new root.
+    pub fn insert_along(path: Vec<RcRefcellAVLNode<K>>, key: K) -> RcRefcellAVLNode<K> {
+        Self::insert_along_keeping(path, key, &mut Vec::new())
+    }
+
+    /// `insert_along` that also rewrites `target`, a path from the root
+    /// through a prefix of `path`, to reach the same node once the rotations
+    /// are done. An empty `target` tracks nothing.
+    pub(crate) fn insert_along_keeping(
+        path: Vec<RcRefcellAVLNode<K>>,
+        key: K,
+        target: &mut Vec<RcRefcellAVLNode<K>>,
+    ) -> RcRefcellAVLNode<K> {
+        let child = AVLNode::new(key).unwrap();
+        let Some(parent) = path.last() else {
+            return child;
+        };
+        let is_left = child.borrow().key < parent.borrow().key;
+        if is_left {
+            parent.borrow_mut().left = Some(child);
+        } else {
+            parent.borrow_mut().right = Some(child);
+        }
+        Self::_rebalance_along(path, target)
+    }
+
+    /// Removes the key of the last node of `path` (root first) without
+    /// comparing keys, and rebalances back up. Returns the new root, the
+    /// removed key and the path to the node now holding the next key, empty
+    /// if it was the largest.
+    pub(crate) fn remove_along(
+        mut path: Vec<RcRefcellAVLNode<K>>,
+    ) -> (OptionNode<K>, K, Vec<RcRefcellAVLNode<K>>) {
+        let node = path.pop().unwrap();
+        let (left, right) = {
+            let this_node = node.borrow();
+            (this_node.left.clone(), this_node.right.clone())
+        };
+        let (unlinked, replacement, removed, mut target) = match (left, right) {
+            (Some(_), Some(right)) => {
+                // The successor has no left child, so it is the one unlinked
+                // after its key moves up here; the next key stays on `node`.
+                path.push(node.clone());
+                let target = path.clone();
+                let mut successor = right;
+                loop {
+                    let left = successor.borrow().left.clone();
+                    let Some(left) = left else { break };
+                    path.push(successor);
+                    successor = left;
                 }
+                let (key, count, successor_right) = {
+                    let successor = successor.borrow();
+                    (successor.key.clone(), successor.count, successor.right.clone())
+                };
+                let mut this_mut = node.borrow_mut();
+                this_mut.count = count;
+                let removed = std::mem::replace(&mut this_mut.key, key);
+                drop(this_mut);
+                (successor, successor_right, removed, target)
             }
+            (left, right) => {
+                // A lone right child is a leaf and holds the next key. Without
+                // one, the next key is the nearest ancestor on whose left
+                // this node hangs.
+                let target = match &right {
+                    Some(right) => path.iter().cloned().chain([right.clone()]).collect(),
+                    None => {
+                        let mut ancestors = path.clone();
+                        let mut child = node.clone();
+                        loop {
+                            let Some(parent) = ancestors.last() else { break Vec::new() };
+                            if parent.borrow().left.as_ref().is_some_and(|left| Rc::ptr_eq(left, &child)) {
+                                break ancestors;
+                            }
+                            child = ancestors.pop().unwrap();
+                        }
+                    }
+                };
+                let removed = node.borrow().key.clone();
+                (node, left.or(right), removed, target)
+            }
+        };
+
+        let Some(parent) = path.last() else {
+            return (replacement, removed, target);
         };
+        Self::_replace_child(parent, &unlinked, replacement);
+        let root = Self::_rebalance_along(path, &mut target);
+        (Some(root), removed, target)
+    }
+
+    /// Rebalances every node of `path` (root first, not empty) from the bottom
+    /// up and returns the new root. `target` runs from the root through a
+    /// prefix of `path`, possibly on below its end, and is rewritten to reach
+    /// the same node afterwards.
+    fn _rebalance_along(mut path: Vec<RcRefcellAVLNode<K>>, target: &mut Vec<RcRefcellAVLNode<K>>) -> RcRefcellAVLNode<K> {
+        let tracked = target.len();
+        // The part of `target` below the nodes still to rebalance, bottom first.
+        let mut below: Vec<_> = target.drain(path.len().min(tracked)..).rev().collect();
+        let mut root = None;
+        while let Some(node) = path.pop() {
+            let rebalanced = if path.len() < tracked {
+                below.push(node.clone());
+                Self::_rebalance_keeping(node.clone(), &mut below)
+            } else {
+                Self::_rebalance(node.clone())
+            };
+            if let Some(parent) = path.last() {
+                Self::_replace_child(parent, &node, Some(rebalanced.clone()));
+            }
+            root = Some(rebalanced);
+        }
+        if tracked > 0 {
+            *target = below.into_iter().rev().collect();
+        }
+        root.unwrap()
+    }
+
+    /// `_rebalance` for a node that `below` (bottom first) ends at, which is
+    /// rewritten to end at the new subtree root instead.
+    fn _rebalance_keeping(node: RcRefcellAVLNode<K>, below: &mut Vec<RcRefcellAVLNode<K>>) -> RcRefcellAVLNode<K> {
+        // Rotations only relink the node, its children and its grandchildren.
+        let mut moved = vec![node.clone()];
+        for child in Self::_children(&node).into_iter().flatten() {
+            moved.extend(Self::_children(&child).into_iter().flatten());
+            moved.push(child);
+        }
+        let subtree = Self::_rebalance(node);
+
+        let goal = below[0].clone();
+        while below.last().is_some_and(|last| moved.iter().any(|node| Rc::ptr_eq(node, last))) {
+            below.pop();
+        }
+        // Everything left in `below` kept its place under the first node of it.
+        let is_goal = |node: &RcRefcellAVLNode<K>| match below.last() {
+            Some(unmoved) => Self::_children(node).iter().flatten().any(|child| Rc::ptr_eq(child, unmoved)),
+            None => Rc::ptr_eq(node, &goal),
+        };
+        let mut top = Vec::new();
+        let found = Self::_descend_within(subtree.clone(), &moved, &is_goal, &mut top);
+        // Carrying on with a stale path would let a cursor insert out of order.
+        assert!(found, "AVLNode: the tracked node left the rebalanced subtree");
+        below.extend(top.into_iter().rev());
+        subtree
+    }
+
+    /// Pushes onto `path` the nodes from `node` down to one that `is_goal`
+    /// accepts, stepping only through `within`. Leaves `path` as it was and
+    /// returns false if there is none.
+    fn _descend_within(
+        node: RcRefcellAVLNode<K>,
+        within: &[RcRefcellAVLNode<K>],
+        is_goal: &impl Fn(&RcRefcellAVLNode<K>) -> bool,
+        path: &mut Vec<RcRefcellAVLNode<K>>,
+    ) -> bool {
+        if is_goal(&node) {
+            path.push(node);
+            return true;
+        }
+        let children = Self::_children(&node);
+        path.push(node);
+        for child in children.into_iter().flatten() {
+            if within.iter().any(|node| Rc::ptr_eq(node, &child)) && Self::_descend_within(child, within, is_goal, path) {
+                return true;
+            }
+        }
+        path.pop();
+        false
+    }
+
+    fn _children(node: &RcRefcellAVLNode<K>) -> [OptionNode<K>; 2] {
+        let this_node = node.borrow();
+        [this_node.left.clone(), this_node.right.clone()]
+    }
+
+    /// Points `parent` at `new` wherever it pointed at `old`.
+    fn _replace_child(parent: &RcRefcellAVLNode<K>, old: &RcRefcellAVLNode<K>, new: OptionNode<K>) {
+        let mut parent = parent.borrow_mut();
+        if parent.left.as_ref().is_some_and(|left| Rc::ptr_eq(left, old)) {
+            parent.left = new;
+        } else {
+            parent.right = new;
+        }
+    }
 
-        if return_node.is_none() {
-            return return_node;
+    /// Makes a node above two finished subtrees and computes its cached
+    /// fields. Does not rebalance.
+    pub(crate) fn join(key: K, left: OptionNode<K>, right: OptionNode<K>) -> RcRefcellAVLNode<K> {
+        let node = AVLNode::new(key).unwrap();
+        {
+            let mut this_node = node.borrow_mut();
+            this_node.left = left;
+            this_node.right = right;
         }
-        let this_node = return_node.unwrap();
+        Self::_update(&node);
+        node
+    }
+
+    /// Makes `pivot` the parent of `left` and `right`, whose keys are all
+    /// smaller and all larger than its key. Walks down the spine of the taller
+    /// tree to a subtree of matching height, so it runs in O(|h(left) - h(right)|).
+    pub(crate) fn join_trees(left: OptionNode<K>, pivot: RcRefcellAVLNode<K>, right: OptionNode<K>) -> RcRefcellAVLNode<K> {
+        let (left_height, right_height) = (Self::_get_height(left.clone()), Self::_get_height(right.clone()));
+        if left_height > right_height + 1 {
+            let left = left.unwrap();
+            let inner = left.borrow_mut().right.take();
+            let joined = Self::join_trees(inner, pivot, right);
+            left.borrow_mut().right = Some(joined);
+            Self::_rebalance(left)
+        } else if right_height > left_height + 1 {
+            let right = right.unwrap();
+            let inner = right.borrow_mut().left.take();
+            let joined = Self::join_trees(left, pivot, inner);
+            right.borrow_mut().left = Some(joined);
+            Self::_rebalance(right)
+        } else {
+            {
+                let mut pivot_node = pivot.borrow_mut();
+                pivot_node.left = left;
+                pivot_node.right = right;
+            }
+            Self::_update(&pivot);
+            pivot
+        }
+    }
+
+    /// Splits the subtree at `node` into the keys less than `key` and the
+    /// rest, in O(log n).
+    pub(crate) fn split<Q>(node: OptionNode<K>, key: &Q) -> (OptionNode<K>, OptionNode<K>)
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let Some(node) = node else {
+            return (None, None);
+        };
+        let (left, right) = {
+            let mut this_node = node.borrow_mut();
+            (this_node.left.take(), this_node.right.take())
+        };
+        let ordering = node.borrow().key.borrow().cmp(key);
+        match ordering {
+            Ordering::Greater => {
+                let (less, rest) = Self::split(left, key);
+                (less, Some(Self::join_trees(rest, node, right)))
+            }
+            Ordering::Less => {
+                let (less, rest) = Self::split(right, key);
+                (Some(Self::join_trees(left, node, less)), rest)
+            }
+            Ordering::Equal => (left, Some(Self::join_trees(None, node, right))),
+        }
+    }
+
+    fn _rebalance(this_node: RcRefcellAVLNode<K>) -> RcRefcellAVLNode<K> {
         let balance_factor = Self::_get_balance_factor(&this_node);
         let return_node = match balance_factor {
             2 => {
@@ -274,10 +607,7 @@ impl AVLNode {
             _ => this_node,
         };
 
-        return_node.borrow_mut().height = max(
-            Self::_get_left_height(&return_node),
-            Self::_get_right_height(&return_node),
-        ) + 1;
-        Some(return_node)
+        Self::_update(&return_node);
+        return_node
     }
 }
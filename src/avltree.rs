@@ -1,41 +1,233 @@
+use std::cell::RefCell;
+use std::fmt::{self, Debug, Display};
+use std::rc::Rc;
+
 use crate::avlnode::{AVLNode, OptionNode};
+use crate::dot::{balance_style, DotNodeStyle};
+use crate::invariant::{validate_balance, validate_shape, InvariantViolation};
+use crate::iter::Iter;
 use crate::node::Node;
-use crate::tree::Tree;
+use crate::render::RenderStyle;
+use crate::setops::impl_set_operators;
+use crate::tree::{collect_sorted, merge_sorted, Tree};
 
-pub struct AVLTree {
-    _root: OptionNode,
+pub struct AVLTree<K> {
+    _root: OptionNode<K>,
 }
 
-impl Tree<AVLNode> for AVLTree {
+impl<K: Ord + Clone + Debug> Tree<K> for AVLTree<K> {
+    type NodeRef = Rc<RefCell<AVLNode<K>>>;
+
     fn new() -> Self {
         Self {_root: None}
     }
 
-    fn get_root(&self) -> &OptionNode {
-        &self._root
+    fn get_root(&self) -> Option<Rc<RefCell<AVLNode<K>>>> {
+        self._root.clone()
+    }
+
+    fn get_left(&self, node: &Rc<RefCell<AVLNode<K>>>) -> Option<Rc<RefCell<AVLNode<K>>>> {
+        node.borrow().get_left().clone()
+    }
+
+    fn get_right(&self, node: &Rc<RefCell<AVLNode<K>>>) -> Option<Rc<RefCell<AVLNode<K>>>> {
+        node.borrow().get_right().clone()
+    }
+
+    fn with_key<R>(&self, node: &Rc<RefCell<AVLNode<K>>>, f: impl FnOnce(&K) -> R) -> R {
+        f(node.borrow().get_key())
+    }
+
+    fn get_node_size(&self, node: &Rc<RefCell<AVLNode<K>>>) -> usize {
+        node.borrow().get_size()
     }
 
-    fn insert(&mut self, key: i64) {
+    fn get_node_count(&self, node: &Rc<RefCell<AVLNode<K>>>) -> usize {
+        node.borrow().get_count()
+    }
+
+    fn get_node_total(&self, node: &Rc<RefCell<AVLNode<K>>>) -> usize {
+        node.borrow().get_total()
+    }
+
+    fn get_node_height(&self, node: &Rc<RefCell<AVLNode<K>>>) -> u32 {
+        node.borrow().get_height()
+    }
+
+    fn count_node_leaves(&self, node: &Rc<RefCell<AVLNode<K>>>) -> u32 {
+        node.borrow().count_leaves()
+    }
+
+    fn insert(&mut self, key: K) -> bool {
+        let len = self.len();
         match self._root.take() {
             Some(root) => self._root = AVLNode::insert(Some(root), key),
             None => self._root = AVLNode::new(key),
         }
+        self.len() > len
     }
 
-    fn delete(&mut self, key: i64) {
-        match self._root.take() {
-            Some(root) => self._root = AVLNode::delete(Some(root), key),
-            None => return,
-        }
+    fn delete<Q>(&mut self, key: &Q) -> Option<K>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (root, removed) = AVLNode::delete(self._root.take(), key);
+        self._root = root;
+        removed
     }
 
-    fn print_tree(&self) {
-        if &self.is_empty() == &true {
-            println!("This tree is empty!");
+    fn insert_at(&mut self, path: Vec<Rc<RefCell<AVLNode<K>>>>, key: K) {
+        if path.is_empty() {
+            self._root = AVLNode::new(key);
         } else {
-            self._root.as_ref().unwrap().borrow()
-                .print_node(&"".to_string(), "Root".to_string(), false);
+            self._root = Some(AVLNode::insert_along(path, key));
+        }
+    }
+
+    fn delete_at(&mut self, path: Vec<Rc<RefCell<AVLNode<K>>>>) -> K {
+        self.remove_at(path).0
+    }
+
+    fn render_sideways(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        match &self._root {
+            Some(root) => root.borrow().render_node(out, "", "Root".to_string(), false),
+            None => Ok(()),
+        }
+    }
+
+    fn dot_node_style(&self, node: &Rc<RefCell<AVLNode<K>>>) -> DotNodeStyle {
+        balance_style(self, node)
+    }
+}
+
+impl<K: Ord + Clone + Debug> AVLTree<K> {
+    /// Builds a balanced tree from keys in ascending order in O(n). Repeated
+    /// keys are kept once.
+    ///
+    /// Panics if a key is smaller than the one before it.
+    pub fn from_sorted_iter(keys: impl IntoIterator<Item = K>) -> Self {
+        Self::from_sorted_vec(collect_sorted(keys, "AVLTree::from_sorted_iter"))
+    }
+
+    /// Builds the tree holding the keys of `left`, `pivot` and those of
+    /// `right`, in O(log n).
+    ///
+    /// Panics unless every key of `left` is less than `pivot` and every key
+    /// of `right` greater.
+    pub fn join(mut left: Self, pivot: K, mut right: Self) -> Self {
+        assert!(
+            left.get_max().is_none_or(|max| max < pivot) && right.get_min().is_none_or(|min| pivot < min),
+            "AVLTree::join: keys are not in order"
+        );
+        let pivot = AVLNode::new(pivot).unwrap();
+        Self::from_root(Some(AVLNode::join_trees(left._root.take(), pivot, right._root.take())))
+    }
+
+    /// Moves the keys greater than or equal to `key` into a new tree, in
+    /// O(log n).
+    pub fn split_off<Q>(&mut self, key: &Q) -> Self
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (less, rest) = AVLNode::split(self._root.take(), key);
+        self._root = less;
+        Self::from_root(rest)
+    }
+
+    /// Moves every key of `other` into this tree, leaving `other` empty. Takes
+    /// O(log n) when all keys of one tree are smaller than those of the
+    /// other and O(n + m) otherwise.
+    pub fn append(&mut self, other: &mut Self) {
+        let (mut left, mut right) = (Self::from_root(self._root.take()), Self::from_root(other._root.take()));
+        if matches!((right.get_max(), left.get_min()), (Some(max), Some(min)) if max < min) {
+            std::mem::swap(&mut left, &mut right);
+        }
+        *self = match right.get_min() {
+            None => left,
+            Some(min) if left.get_max().is_none_or(|max| max < min) => {
+                let pivot = right.delete(&min).unwrap();
+                Self::join(left, pivot, right)
+            }
+            Some(_) => Self::from_sorted_vec(merge_sorted(left.in_order_traversal(), right.in_order_traversal())),
+        };
+    }
+
+    pub(crate) fn from_root(root: OptionNode<K>) -> Self {
+        AVLTree { _root: root }
+    }
+
+    /// `insert_at` that also keeps `position`, a path from the root through
+    /// a prefix of `path`, on the same node. An empty `position` is ignored.
+    pub(crate) fn insert_at_keeping(&mut self, path: Vec<Rc<RefCell<AVLNode<K>>>>, key: K, position: &mut Vec<Rc<RefCell<AVLNode<K>>>>) {
+        self._root = Some(AVLNode::insert_along_keeping(path, key, position));
+    }
+
+    /// Removes the key at the end of `path` without searching for it.
+    /// Returns the key and the path to the next one, empty if there is none.
+    pub(crate) fn remove_at(&mut self, path: Vec<Rc<RefCell<AVLNode<K>>>>) -> (K, Vec<Rc<RefCell<AVLNode<K>>>>) {
+        let (root, removed, next) = AVLNode::remove_along(path);
+        self._root = root;
+        (removed, next)
+    }
+
+    /// Builds a balanced tree from strictly ascending keys in O(n).
+    pub(crate) fn from_sorted_vec(keys: Vec<K>) -> Self {
+        let len = keys.len();
+        Self::from_root(Self::_build_sorted(&mut keys.into_iter(), len))
+    }
+
+    fn _build_sorted(keys: &mut impl Iterator<Item = K>, len: usize) -> OptionNode<K> {
+        if len == 0 {
+            return None;
         }
+        let left = Self::_build_sorted(keys, len / 2);
+        let key = keys.next().unwrap();
+        let right = Self::_build_sorted(keys, len - len / 2 - 1);
+        Some(AVLNode::join(key, left, right))
+    }
+
+    /// Checks key order, the cached fields and that every node is balanced.
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>> {
+        validate_shape(self)?;
+        validate_balance(self)
+    }
+}
+
+impl<K: Ord + Clone + Debug> Display for AVLTree<K> {
+    /// Draws the tree in `RenderStyle::Unicode`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render(f, RenderStyle::Unicode)
+    }
+}
+
+impl_set_operators!(AVLTree);
+
+impl<K: Ord + Clone + Debug> FromIterator<K> for AVLTree<K> {
+    /// Sorts the keys and builds the tree in one pass, without rotations.
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut keys: Vec<K> = iter.into_iter().collect();
+        keys.sort();
+        keys.dedup();
+        Self::from_sorted_vec(keys)
+    }
+}
+
+impl<K: Ord + Clone + Debug> Extend<K> for AVLTree<K> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+impl<'a, K: Ord + Clone + Debug> IntoIterator for &'a AVLTree<K> {
+    type Item = K;
+    type IntoIter = Iter<'a, K, AVLTree<K>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
 }
 
@@ -64,17 +256,18 @@ mod test {
         
         // Initial checks
         assert_eq!(avl_tree.get_height(), 0);
-        assert_eq!(avl_tree.is_empty(), true);
+        assert!(avl_tree.is_empty());
         assert_eq!(avl_tree.count_nodes(), 0);
         // Insert items
         for number in input_slice {
             avl_tree.insert(*number);
+            avl_tree.validate().unwrap();
         }
         // Check tree properties
         assert_eq!(avl_tree.count_nodes(), input_slice.len().try_into().unwrap());
         assert_eq!(avl_tree.get_min().unwrap(), *input_slice.iter().min().unwrap());
         assert_eq!(avl_tree.get_max().unwrap(), *input_slice.iter().max().unwrap());
-        assert_eq!(avl_tree.is_empty(), false);
+        assert!(!avl_tree.is_empty());
         assert_eq!(avl_tree.get_height(), 5);
         assert_eq!(avl_tree.count_leaves(), 9);
         assert_eq!(avl_tree.in_order_traversal(), sorted_input);
@@ -82,34 +275,107 @@ mod test {
 
         // Check if items are in the tree
         for number in input_slice {
-            assert_eq!(avl_tree.contain(*number), true);
+            assert!(avl_tree.contain(number));
         }
         // Delete items
         for number in to_delete_slice {
-            avl_tree.delete(*number);
+            avl_tree.delete(number);
+            avl_tree.validate().unwrap();
         }
         // Check tree properties after deletion
         assert_eq!(avl_tree.count_nodes(), remaining_slice.len().try_into().unwrap());
         assert_eq!(avl_tree.get_min().unwrap(), *remaining_slice.iter().min().unwrap());
         assert_eq!(avl_tree.get_max().unwrap(), *remaining_slice.iter().max().unwrap());
-        assert_eq!(avl_tree.is_empty(), false);
+        assert!(!avl_tree.is_empty());
         assert_eq!(avl_tree.get_height(), 4);
         assert_eq!(avl_tree.count_leaves(), 5);
         // Check if items are not in the tree anymore
         for number in to_delete_slice {
-            assert_eq!(avl_tree.contain(*number), false);
+            assert!(!avl_tree.contain(number));
         }
         // Check if other items are still in the tree
         for number in remaining_slice {
-            assert_eq!(avl_tree.contain(*number), true);
+            assert!(avl_tree.contain(number));
         }
         // Delete all items
         for number in input_slice {
-            avl_tree.delete(*number);
+            avl_tree.delete(number);
+            avl_tree.validate().unwrap();
         }
         // Check tree properties after deletion
         assert_eq!(avl_tree.get_height(), 0);
-        assert_eq!(avl_tree.is_empty(), true);
+        assert!(avl_tree.is_empty());
         assert_eq!(avl_tree.count_nodes(), 0);
     }
-}
\ No newline at end of file
+    #[test]
+    fn test_avltree_string_keys() {
+        let mut avl_tree: avltree::AVLTree<String> = avltree::AVLTree::new();
+        for word in ["pear", "apple", "fig", "banana", "cherry"] {
+            avl_tree.insert(word.to_string());
+            avl_tree.validate().unwrap();
+        }
+        assert!(!avl_tree.insert("apple".to_string()));
+        assert_eq!(avl_tree.delete(&"fig".to_string()), Some("fig".to_string()));
+        assert_eq!(avl_tree.delete(&"fig".to_string()), None);
+        avl_tree.validate().unwrap();
+
+        assert_eq!(avl_tree.get_min().unwrap(), "apple");
+        assert_eq!(avl_tree.get_max().unwrap(), "pear");
+        assert!(avl_tree.contain(&"cherry".to_string()));
+        assert!(!avl_tree.contain(&"fig".to_string()));
+        assert_eq!(avl_tree.in_order_traversal(), vec!["apple", "banana", "cherry", "pear"]);
+    }
+    #[test]
+    fn test_avltree_from_sorted_iter() {
+        for size in 0..64u32 {
+            let avl_tree = avltree::AVLTree::from_sorted_iter(0..size);
+            avl_tree.validate().unwrap();
+            assert_eq!(avl_tree.len(), size as usize);
+            assert_eq!(avl_tree.get_height(), (size + 1).next_power_of_two().trailing_zeros());
+        }
+        let mut avl_tree: avltree::AVLTree<i64> = [5, 1, 5, 3].into_iter().collect();
+        assert_eq!(avl_tree.in_order_traversal(), vec![1, 3, 5]);
+        avl_tree.extend([4, 2, 3]);
+        avl_tree.validate().unwrap();
+        assert_eq!(avl_tree.in_order_traversal(), vec![1, 2, 3, 4, 5]);
+    }
+    #[test]
+    fn test_avltree_split_join() {
+        let build = |size: i64| {
+            let mut tree: avltree::AVLTree<i64> = avltree::AVLTree::new();
+            for number in 0..size {
+                tree.insert(number * 919 % 1000);
+            }
+            tree
+        };
+        for size in 0..60 {
+            let all = build(size).in_order_traversal();
+            for key in [0, 250, 500, 999, 1000] {
+                let mut avl_tree = build(size);
+                let mut right = avl_tree.split_off(&key);
+                avl_tree.validate().unwrap();
+                right.validate().unwrap();
+                assert!(avl_tree.iter().all(|number| number < key) && right.iter().all(|number| number >= key));
+                assert_eq!(avl_tree.len() + right.len(), all.len());
+
+                if let Some(pivot) = right.get_min() {
+                    right.delete(&pivot);
+                    let joined = avltree::AVLTree::join(avl_tree, pivot, right);
+                    joined.validate().unwrap();
+                    assert_eq!(joined.in_order_traversal(), all);
+                }
+            }
+        }
+
+        let mut avl_tree = build(40);
+        let mut other: avltree::AVLTree<i64> = (500..700).collect();
+        avl_tree.append(&mut other);
+        avl_tree.validate().unwrap();
+        assert!(other.is_empty());
+        let mut expected = build(40).in_order_traversal();
+        expected.extend(500..700);
+        expected.sort();
+        expected.dedup();
+        assert_eq!(avl_tree.in_order_traversal(), expected);
+    }
+}
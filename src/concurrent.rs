@@ -0,0 +1,148 @@
+use std::fmt::Debug;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::arenarbtree::ArenaRBTree;
+use crate::invariant::InvariantViolation;
+use crate::tree::Tree;
+
+/// `ArenaRBTree` behind a `RwLock`, for sharing between threads through an
+/// `Arc`. Lookups take the read lock and run in parallel; updates take the
+/// write lock. The arena trees hold no `Rc` or `RefCell`, so they are
+/// `Send + Sync` whenever `K` is.
+pub struct ConcurrentRBTree<K> {
+    tree: RwLock<ArenaRBTree<K>>,
+}
+
+impl<K: Ord + Clone + Debug> ConcurrentRBTree<K> {
+    pub fn new() -> Self {
+        ConcurrentRBTree {
+            tree: RwLock::new(ArenaRBTree::new()),
+        }
+    }
+
+    pub fn insert(&self, key: K) -> bool {
+        self.write().insert(key)
+    }
+
+    pub fn delete<Q>(&self, key: &Q) -> Option<K>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.write().delete(key)
+    }
+
+    pub fn contain<Q>(&self, key: &Q) -> bool
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.read().contain(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Holds the read lock, so several queries see the same version.
+    ///
+    /// Panics if a writer panicked while holding the lock, since the tree
+    /// may have been left half updated.
+    pub fn read(&self) -> RwLockReadGuard<'_, ArenaRBTree<K>> {
+        self.tree.read().expect("ConcurrentRBTree: a writer panicked")
+    }
+
+    /// Holds the write lock, for a batch of updates applied at once.
+    pub fn write(&self) -> RwLockWriteGuard<'_, ArenaRBTree<K>> {
+        self.tree.write().expect("ConcurrentRBTree: a writer panicked")
+    }
+
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>> {
+        self.read().validate()
+    }
+
+    pub fn into_inner(self) -> ArenaRBTree<K> {
+        self.tree.into_inner().expect("ConcurrentRBTree: a writer panicked")
+    }
+}
+
+impl<K: Ord + Clone + Debug> Default for ConcurrentRBTree<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> From<ArenaRBTree<K>> for ConcurrentRBTree<K> {
+    fn from(tree: ArenaRBTree<K>) -> Self {
+        ConcurrentRBTree { tree: RwLock::new(tree) }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::arenaavltree::ArenaAVLTree;
+    use crate::arenarbtree::ArenaRBTree;
+    use crate::concurrent::ConcurrentRBTree;
+    use crate::tree::Tree;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_concurrent_rbtree() {
+        assert_send_sync::<ArenaRBTree<String>>();
+        assert_send_sync::<ArenaAVLTree<String>>();
+        assert_send_sync::<ConcurrentRBTree<String>>();
+
+        const THREADS: i64 = 8;
+        const KEYS: i64 = 2000;
+        let tree: Arc<ConcurrentRBTree<i64>> = Arc::new(ConcurrentRBTree::new());
+        let workers: Vec<_> = (0..THREADS)
+            .map(|thread_index| {
+                let tree = Arc::clone(&tree);
+                thread::spawn(move || {
+                    // Each writer owns the keys congruent to its index.
+                    let keys = (0..KEYS).map(|number| number * THREADS + thread_index);
+                    for key in keys.clone() {
+                        assert!(tree.insert(key));
+                        assert!(tree.contain(&key));
+                    }
+                    for key in keys.clone().filter(|key| key % 3 == 0) {
+                        assert_eq!(tree.delete(&key), Some(key));
+                        assert!(!tree.contain(&key));
+                    }
+                    for key in keys {
+                        assert_eq!(tree.contain(&key), key % 3 != 0);
+                    }
+                })
+            })
+            .collect();
+        let readers: Vec<_> = (0..2)
+            .map(|_| {
+                let tree = Arc::clone(&tree);
+                thread::spawn(move || {
+                    for _ in 0..200 {
+                        let snapshot = tree.read();
+                        assert_eq!(snapshot.iter().count(), snapshot.len());
+                    }
+                })
+            })
+            .collect();
+        for handle in workers.into_iter().chain(readers) {
+            handle.join().unwrap();
+        }
+
+        tree.validate().unwrap();
+        let expected: Vec<i64> = (0..KEYS * THREADS).filter(|key| key % 3 != 0).collect();
+        assert_eq!(tree.len(), expected.len());
+        let tree = Arc::into_inner(tree).unwrap().into_inner();
+        assert_eq!(tree.in_order_traversal(), expected);
+    }
+}
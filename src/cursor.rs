@@ -0,0 +1,548 @@
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+use std::rc::Rc;
+
+use crate::avltree::AVLTree;
+use crate::rbnode::RcRefcellRBTNode;
+use crate::rbtree::RBTree;
+use crate::tree::Tree;
+
+/// Trees a `Cursor` can walk. A position is a node plus whatever it takes to
+/// reach the node's neighbours from it.
+pub trait CursorTree<K: Ord + Clone>: Tree<K> + Sized {
+    type Position: Clone;
+
+    fn position_node(position: &Self::Position) -> Self::NodeRef;
+
+    /// Position of `key`, if the tree holds it.
+    fn locate<Q>(&self, key: &Q) -> Option<Self::Position>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized;
+
+    /// Position of the smallest key, or of the largest if `!first`.
+    fn end_position(&self, first: bool) -> Option<Self::Position>;
+
+    /// Position of the next key, or of the previous one if `!forward`.
+    fn step(&self, position: Self::Position, forward: bool) -> Option<Self::Position>;
+
+    /// Inserts `key` as a child of the node at `parent`, or as the root if
+    /// there is none, and keeps `position` on the node it was on.
+    fn insert_child(&mut self, parent: Option<Self::Position>, key: K, position: &mut Option<Self::Position>);
+
+    /// Removes the key at `position` without searching for it. Returns the
+    /// key and the position of the next one.
+    fn remove_at(&mut self, position: Self::Position) -> (K, Option<Self::Position>);
+
+    fn cursor_front(&self) -> Cursor<'_, K, Self> {
+        Cursor {
+            position: self.end_position(true),
+            tree: self,
+        }
+    }
+
+    fn cursor_back(&self) -> Cursor<'_, K, Self> {
+        Cursor {
+            position: self.end_position(false),
+            tree: self,
+        }
+    }
+
+    /// Cursor on `key`, or on the ghost position if the tree lacks it.
+    fn cursor_at<Q>(&self, key: &Q) -> Cursor<'_, K, Self>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Cursor {
+            position: self.locate(key),
+            tree: self,
+        }
+    }
+
+    fn cursor_front_mut(&mut self) -> CursorMut<'_, K, Self> {
+        CursorMut {
+            position: self.end_position(true),
+            tree: self,
+        }
+    }
+
+    fn cursor_back_mut(&mut self) -> CursorMut<'_, K, Self> {
+        CursorMut {
+            position: self.end_position(false),
+            tree: self,
+        }
+    }
+
+    fn cursor_at_mut<Q>(&mut self, key: &Q) -> CursorMut<'_, K, Self>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        CursorMut {
+            position: self.locate(key),
+            tree: self,
+        }
+    }
+}
+
+/// Red-black nodes link to their parent, so a position is just the node and
+/// each step costs O(1) amortized.
+impl<K: Ord + Clone + Debug> CursorTree<K> for RBTree<K> {
+    type Position = RcRefcellRBTNode<K>;
+
+    fn position_node(position: &RcRefcellRBTNode<K>) -> RcRefcellRBTNode<K> {
+        position.clone()
+    }
+
+    fn locate<Q>(&self, key: &Q) -> Option<RcRefcellRBTNode<K>>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match self.search(key) {
+            (true, node) => node,
+            _ => None,
+        }
+    }
+
+    fn end_position(&self, first: bool) -> Option<RcRefcellRBTNode<K>> {
+        self.get_root().map(|root| _rb_extreme(root, first))
+    }
+
+    fn step(&self, node: RcRefcellRBTNode<K>, forward: bool) -> Option<RcRefcellRBTNode<K>> {
+        let child = if forward { node.borrow().right.clone() } else { node.borrow().left.clone() };
+        if let Some(child) = child {
+            return Some(_rb_extreme(child, forward));
+        }
+        // Climb until we leave a subtree on the side we are moving away from.
+        let mut current = node;
+        loop {
+            let parent = current.borrow().get_parent()?;
+            let is_left = parent.borrow().left.as_ref().is_some_and(|left| Rc::ptr_eq(left, &current));
+            if is_left == forward {
+                return Some(parent);
+            }
+            current = parent;
+        }
+    }
+
+    /// Rotations relink nodes but never move keys between them, so
+    /// `position` needs no update.
+    fn insert_child(&mut self, parent: Option<RcRefcellRBTNode<K>>, key: K, _position: &mut Option<RcRefcellRBTNode<K>>) {
+        self.insert_below(parent, key);
+    }
+
+    /// Panics if the tree turns out to be corrupted, like `delete`.
+    fn remove_at(&mut self, node: RcRefcellRBTNode<K>) -> (K, Option<RcRefcellRBTNode<K>>) {
+        match self.delete_node(&node) {
+            Ok(removed) => removed,
+            Err(error) => panic!("RBTree::delete: {}", error),
+        }
+    }
+}
+
+/// AVL nodes have no parent links, so a position is the whole path from the
+/// root; steps are still O(1) amortized.
+impl<K: Ord + Clone + Debug> CursorTree<K> for AVLTree<K> {
+    type Position = Vec<Self::NodeRef>;
+
+    fn position_node(path: &Vec<Self::NodeRef>) -> Self::NodeRef {
+        path.last().unwrap().clone()
+    }
+
+    fn locate<Q>(&self, key: &Q) -> Option<Vec<Self::NodeRef>>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match self.search_path(key) {
+            (true, path) => Some(path),
+            _ => None,
+        }
+    }
+
+    fn end_position(&self, first: bool) -> Option<Vec<Self::NodeRef>> {
+        let root = self.get_root()?;
+        let mut path = Vec::new();
+        _push_extreme(self, &mut path, root, first);
+        Some(path)
+    }
+
+    fn step(&self, mut path: Vec<Self::NodeRef>, forward: bool) -> Option<Vec<Self::NodeRef>> {
+        let node = path.last()?.clone();
+        let child = if forward { self.get_right(&node) } else { self.get_left(&node) };
+        if let Some(child) = child {
+            _push_extreme(self, &mut path, child, forward);
+            return Some(path);
+        }
+        loop {
+            let child = path.pop()?;
+            let parent = path.last()?;
+            let is_left = self.get_left(parent).is_some_and(|left| Rc::ptr_eq(&left, &child));
+            if is_left == forward {
+                return Some(path);
+            }
+        }
+    }
+
+    /// The cursor's node is the new key's parent or one of its ancestors,
+    /// so its path is carried through the rotations.
+    fn insert_child(&mut self, parent: Option<Vec<Self::NodeRef>>, key: K, position: &mut Option<Vec<Self::NodeRef>>) {
+        let mut kept = position.take().unwrap_or_default();
+        self.insert_at_keeping(parent.unwrap_or_default(), key, &mut kept);
+        *position = (!kept.is_empty()).then_some(kept);
+    }
+
+    fn remove_at(&mut self, path: Vec<Self::NodeRef>) -> (K, Option<Vec<Self::NodeRef>>) {
+        let (removed, next) = AVLTree::remove_at(self, path);
+        (removed, (!next.is_empty()).then_some(next))
+    }
+}
+
+/// Leftmost node below `node` when `first`, rightmost otherwise.
+fn _rb_extreme<K>(mut node: RcRefcellRBTNode<K>, first: bool) -> RcRefcellRBTNode<K> {
+    loop {
+        let child = if first { node.borrow().left.clone() } else { node.borrow().right.clone() };
+        match child {
+            Some(child) => node = child,
+            None => return node,
+        }
+    }
+}
+
+/// Pushes `node` and its left spine, or its right spine if `!first`.
+fn _push_extreme<K, T>(tree: &T, path: &mut Vec<T::NodeRef>, node: T::NodeRef, first: bool)
+where
+    K: Ord + Clone,
+    T: Tree<K>,
+{
+    let mut current = Some(node);
+    while let Some(node) = current {
+        current = if first { tree.get_left(&node) } else { tree.get_right(&node) };
+        path.push(node);
+    }
+}
+
+/// Returned by `CursorMut::insert_after` when the key would not land between
+/// the current key and the next one. Hands the key back.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnorderedKeyError<K> {
+    pub key: K,
+}
+
+impl<K: Debug> Display for UnorderedKeyError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "key {:?} does not fit after the cursor", self.key)
+    }
+}
+
+impl<K: Debug> Error for UnorderedKeyError<K> {}
+
+/// Read-only cursor. Besides the keys it can stand on a ghost position past
+/// both ends: moving forward from the last key or back from the first one
+/// reaches it, and moving from it wraps to the other end.
+pub struct Cursor<'a, K, T: CursorTree<K>>
+where
+    K: Ord + Clone,
+{
+    tree: &'a T,
+    position: Option<T::Position>,
+}
+
+impl<K: Ord + Clone, T: CursorTree<K>> Cursor<'_, K, T> {
+    /// The key under the cursor, `None` on the ghost position.
+    pub fn key(&self) -> Option<K> {
+        self.position.as_ref().map(|position| self.tree.get_key(&T::position_node(position)))
+    }
+
+    pub fn move_next(&mut self) {
+        self.position = _move(self.tree, self.position.take(), true);
+    }
+
+    pub fn move_prev(&mut self) {
+        self.position = _move(self.tree, self.position.take(), false);
+    }
+}
+
+/// Cursor that can also insert and remove keys next to its position.
+pub struct CursorMut<'a, K, T: CursorTree<K>>
+where
+    K: Ord + Clone,
+{
+    tree: &'a mut T,
+    position: Option<T::Position>,
+}
+
+impl<K: Ord + Clone, T: CursorTree<K>> CursorMut<'_, K, T> {
+    pub fn key(&self) -> Option<K> {
+        self.position.as_ref().map(|position| self.tree.get_key(&T::position_node(position)))
+    }
+
+    pub fn move_next(&mut self) {
+        self.position = _move(self.tree, self.position.take(), true);
+    }
+
+    pub fn move_prev(&mut self) {
+        self.position = _move(self.tree, self.position.take(), false);
+    }
+
+    /// Inserts `key` right after the cursor, or at the front when it is on
+    /// the ghost position, without searching for its place. The cursor does not
+    /// move. Fails unless `key` falls strictly between the current key and
+    /// the next one.
+    pub fn insert_after(&mut self, key: K) -> Result<(), UnorderedKeyError<K>> {
+        let next = _move(&*self.tree, self.position.clone(), true);
+        let is_after_current = self
+            .position
+            .as_ref()
+            .is_none_or(|position| self.tree.with_key(&T::position_node(position), |current| *current < key));
+        let is_before_next = next
+            .as_ref()
+            .is_none_or(|position| self.tree.with_key(&T::position_node(position), |next| key < *next));
+        if !is_after_current || !is_before_next {
+            return Err(UnorderedKeyError { key });
+        }
+
+        // The new key becomes the right child of the current node if that is
+        // free, and otherwise the left child of the next node, which then has
+        // none.
+        let parent = match &self.position {
+            Some(position) if self.tree.get_right(&T::position_node(position)).is_none() => Some(position.clone()),
+            _ => next,
+        };
+        self.tree.insert_child(parent, key, &mut self.position);
+        Ok(())
+    }
+
+    /// Removes the current key, without searching for it, and moves to the
+    /// next one, or to the ghost position if it was the last. Returns `None`
+    /// on the ghost position.
+    pub fn remove_current(&mut self) -> Option<K> {
+        let position = self.position.take()?;
+        let (removed, next) = self.tree.remove_at(position);
+        self.position = next;
+        Some(removed)
+    }
+}
+
+fn _move<K, T>(tree: &T, position: Option<T::Position>, forward: bool) -> Option<T::Position>
+where
+    K: Ord + Clone,
+    T: CursorTree<K>,
+{
+    match position {
+        Some(position) => tree.step(position, forward),
+        None => tree.end_position(forward),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use proptest::prelude::*;
+
+    use crate::avltree::AVLTree;
+    use crate::cursor::{CursorTree, UnorderedKeyError};
+    use crate::rbtree::RBTree;
+    use crate::tree::Tree;
+
+    fn check_cursor<T: CursorTree<i64>>(mut tree: T, validate: fn(&T)) {
+        let mut cursor = tree.cursor_front();
+        assert_eq!(cursor.key(), None);
+        cursor.move_next();
+        assert_eq!(cursor.key(), None);
+
+        for number in (0..50).map(|number| number * 2) {
+            tree.insert(number);
+        }
+        let mut cursor = tree.cursor_front();
+        let mut forward = Vec::new();
+        while let Some(key) = cursor.key() {
+            forward.push(key);
+            cursor.move_next();
+        }
+        assert_eq!(forward, (0..50).map(|number| number * 2).collect::<Vec<_>>());
+        cursor.move_next();
+        assert_eq!(cursor.key(), Some(0));
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.key(), Some(98));
+
+        let mut cursor = tree.cursor_at(&40);
+        cursor.move_prev();
+        assert_eq!(cursor.key(), Some(38));
+        assert_eq!(tree.cursor_at(&41).key(), None);
+        assert_eq!(tree.cursor_back().key(), Some(98));
+
+        let mut cursor = tree.cursor_at_mut(&40);
+        assert_eq!(cursor.insert_after(42), Err(UnorderedKeyError { key: 42 }));
+        assert_eq!(cursor.insert_after(39), Err(UnorderedKeyError { key: 39 }));
+        cursor.insert_after(41).unwrap();
+        assert_eq!(cursor.key(), Some(40));
+        cursor.move_next();
+        assert_eq!(cursor.key(), Some(41));
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        // Keep filling the odd gaps from here to the end.
+        while let Some(key) = cursor.key() {
+            if key % 2 == 0 && key < 98 {
+                cursor.insert_after(key + 1).unwrap();
+            }
+            cursor.move_next();
+        }
+        cursor.insert_after(-1).unwrap();
+        validate(&tree);
+        assert_eq!(tree.len(), 77);
+
+        let mut cursor = tree.cursor_front_mut();
+        while let Some(key) = cursor.key() {
+            if key % 2 != 0 {
+                assert_eq!(cursor.remove_current(), Some(key));
+            } else {
+                cursor.move_next();
+            }
+        }
+        assert_eq!(cursor.remove_current(), None);
+        let mut cursor = tree.cursor_back_mut();
+        assert_eq!(cursor.remove_current(), Some(98));
+        assert_eq!(cursor.key(), None);
+        validate(&tree);
+        assert_eq!(tree.in_order_traversal(), (0..49).map(|number| number * 2).collect::<Vec<_>>());
+    }
+
+    /// Removes the root, which has two children, until the tree is small,
+    /// then everything else from the front.
+    fn check_remove_current<T: CursorTree<i64>>(mut tree: T, validate: fn(&T)) {
+        let mut expected: Vec<i64> = (1..=63).collect();
+        for number in &expected {
+            tree.insert(*number);
+        }
+        loop {
+            let root = tree.get_root().unwrap();
+            if tree.get_left(&root).is_none() || tree.get_right(&root).is_none() {
+                break;
+            }
+            let key = tree.get_key(&root);
+            let index = expected.binary_search(&key).unwrap();
+            let mut cursor = tree.cursor_at_mut(&key);
+            assert_eq!(cursor.remove_current(), Some(key));
+            expected.remove(index);
+            assert_eq!(cursor.key(), Some(expected[index]));
+            cursor.move_prev();
+            assert_eq!(cursor.key(), Some(expected[index - 1]));
+            cursor.move_next();
+            cursor.move_next();
+            assert_eq!(cursor.key(), expected.get(index + 1).copied());
+            validate(&tree);
+        }
+        assert!(expected.len() < 4);
+
+        for number in 100..140 {
+            tree.insert(number);
+            expected.push(number);
+        }
+        let mut cursor = tree.cursor_front_mut();
+        for (index, key) in expected.iter().enumerate() {
+            assert_eq!(cursor.remove_current(), Some(*key));
+            assert_eq!(cursor.key(), expected.get(index + 1).copied());
+            validate(cursor.tree);
+        }
+        assert!(tree.is_empty());
+
+        // A root with only a right child.
+        tree.insert(1);
+        tree.insert(2);
+        let root = tree.get_root().unwrap();
+        assert!(tree.get_left(&root).is_none() && tree.get_right(&root).is_some());
+        let mut cursor = tree.cursor_front_mut();
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.key(), Some(2));
+        cursor.move_prev();
+        assert_eq!(cursor.key(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert_eq!(cursor.key(), None);
+        validate(&tree);
+        assert!(tree.is_empty());
+    }
+
+    /// Runs cursor steps against a `BTreeSet`: 0 moves forward, 1 back, 2
+    /// inserts `key` after the cursor, 3 removes the current key.
+    fn check_cursor_steps<T: CursorTree<i64>>(
+        keys: &[i64],
+        steps: &[(u8, i64)],
+        validate: fn(&T),
+    ) -> Result<(), TestCaseError> {
+        let mut tree = T::new();
+        let mut model = BTreeSet::new();
+        for key in keys {
+            tree.insert(*key);
+            model.insert(*key);
+        }
+        let mut cursor = tree.cursor_front_mut();
+        let mut current = model.first().copied();
+        for (step, key) in steps {
+            let next = match current {
+                Some(current) => model.range(current + 1..).next().copied(),
+                None => model.first().copied(),
+            };
+            match step {
+                0 => {
+                    cursor.move_next();
+                    current = next;
+                }
+                1 => {
+                    cursor.move_prev();
+                    current = match current {
+                        Some(current) => model.range(..current).next_back().copied(),
+                        None => model.last().copied(),
+                    };
+                }
+                2 => {
+                    let fits = current.is_none_or(|current| current < *key) && next.is_none_or(|next| *key < next);
+                    if fits {
+                        prop_assert_eq!(cursor.insert_after(*key), Ok(()));
+                        model.insert(*key);
+                    } else {
+                        prop_assert_eq!(cursor.insert_after(*key), Err(UnorderedKeyError { key: *key }));
+                    }
+                }
+                _ => {
+                    prop_assert_eq!(cursor.remove_current(), current);
+                    if let Some(removed) = current {
+                        model.remove(&removed);
+                        current = next;
+                    }
+                }
+            }
+            prop_assert_eq!(cursor.key(), current);
+            validate(cursor.tree);
+        }
+        prop_assert_eq!(tree.in_order_traversal(), model.into_iter().collect::<Vec<_>>());
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn test_cursor_matches_model(
+            keys in prop::collection::vec(0i64..200, 0..60),
+            steps in prop::collection::vec((0u8..4, 0i64..200), 0..200),
+        ) {
+            check_cursor_steps::<RBTree<i64>>(&keys, &steps, |tree| tree.validate().unwrap())?;
+            check_cursor_steps::<AVLTree<i64>>(&keys, &steps, |tree| tree.validate().unwrap())?;
+        }
+    }
+
+    #[test]
+    fn test_cursor() {
+        check_cursor(RBTree::new(), |tree| tree.validate().unwrap());
+        check_cursor(AVLTree::new(), |tree| tree.validate().unwrap());
+        check_remove_current(RBTree::new(), |tree| tree.validate().unwrap());
+        check_remove_current(AVLTree::new(), |tree| tree.validate().unwrap());
+    }
+}
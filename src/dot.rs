@@ -0,0 +1,146 @@
+use std::fmt::{Debug, Write};
+
+use crate::rbnode::NodeColor;
+use crate::tree::Tree;
+
+/// Options for `Tree::to_dot_with`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DotOptions {
+    /// Draw missing children as NIL leaves.
+    pub show_nil: bool,
+}
+
+/// What a tree adds to the default rendering of one node: an extra label
+/// line and Graphviz attributes.
+#[derive(Clone, Debug, Default)]
+pub struct DotNodeStyle {
+    pub annotation: Option<String>,
+    pub attributes: Vec<(&'static str, &'static str)>,
+}
+
+/// Fills red-black nodes with their color.
+pub(crate) fn color_style(color: &NodeColor) -> DotNodeStyle {
+    let fillcolor = match color {
+        NodeColor::Red => "red",
+        NodeColor::Black => "black",
+    };
+    DotNodeStyle {
+        annotation: None,
+        attributes: vec![("style", "filled"), ("fillcolor", fillcolor), ("fontcolor", "white")],
+    }
+}
+
+/// Annotates AVL nodes with their height and balance factor.
+pub(crate) fn balance_style<K, T>(tree: &T, node: &T::NodeRef) -> DotNodeStyle
+where
+    K: Ord + Clone,
+    T: Tree<K>,
+{
+    let height = |child: Option<T::NodeRef>| child.map_or(0, |child| tree.get_node_height(&child) as i64);
+    let balance_factor = height(tree.get_left(node)) - height(tree.get_right(node));
+    DotNodeStyle {
+        annotation: Some(format!("h={} bf={}", tree.get_node_height(node), balance_factor)),
+        attributes: Vec::new(),
+    }
+}
+
+pub(crate) fn write_dot<K, T>(tree: &T, options: &DotOptions) -> String
+where
+    K: Ord + Clone + Debug,
+    T: Tree<K>,
+{
+    let mut dot = String::from("digraph Tree {\n    node [shape=circle];\n");
+    if let Some(root) = tree.get_root() {
+        let mut next_id = 1;
+        _write_subtree(tree, options, &mut dot, &root, 0, &mut next_id);
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Writes `node` as `n<id>` and then its children in pre-order, numbering
+/// them from `next_id`.
+fn _write_subtree<K, T>(tree: &T, options: &DotOptions, dot: &mut String, node: &T::NodeRef, id: usize, next_id: &mut usize)
+where
+    K: Ord + Clone + Debug,
+    T: Tree<K>,
+{
+    _write_node(tree, dot, node, id);
+    for child in [tree.get_left(node), tree.get_right(node)] {
+        let child_id = *next_id;
+        match child {
+            Some(child) => {
+                *next_id += 1;
+                writeln!(dot, "    n{} -> n{};", id, child_id).unwrap();
+                _write_subtree(tree, options, dot, &child, child_id, next_id);
+            }
+            None if options.show_nil => {
+                *next_id += 1;
+                writeln!(dot, "    n{} [label=\"NIL\", shape=box, style=filled, fillcolor=black, fontcolor=white, fontsize=8];", child_id).unwrap();
+                writeln!(dot, "    n{} -> n{};", id, child_id).unwrap();
+            }
+            None => {}
+        }
+    }
+}
+
+fn _write_node<K, T>(tree: &T, dot: &mut String, node: &T::NodeRef, id: usize)
+where
+    K: Ord + Clone + Debug,
+    T: Tree<K>,
+{
+    let style = tree.dot_node_style(node);
+    let mut label = tree.with_key(node, |key| _escape(&format!("{:?}", key)));
+    if let Some(annotation) = style.annotation {
+        label.push_str("\\n");
+        label.push_str(&_escape(&annotation));
+    }
+    write!(dot, "    n{} [label=\"{}\"", id, label).unwrap();
+    for (name, value) in style.attributes {
+        write!(dot, ", {}={}", name, value).unwrap();
+    }
+    dot.push_str("];\n");
+}
+
+fn _escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::avltree::AVLTree;
+    use crate::dot::DotOptions;
+    use crate::rbtree::RBTree;
+    use crate::tree::Tree;
+
+    #[test]
+    fn test_to_dot() {
+        let mut rb_tree: RBTree<i64> = RBTree::new();
+        assert_eq!(rb_tree.to_dot(), "digraph Tree {\n    node [shape=circle];\n}\n");
+        for number in [5, 3, 8] {
+            rb_tree.insert(number);
+        }
+        assert_eq!(
+            rb_tree.to_dot(),
+            "digraph Tree {\n    node [shape=circle];\n\
+             \x20   n0 [label=\"5\", style=filled, fillcolor=black, fontcolor=white];\n\
+             \x20   n0 -> n1;\n\
+             \x20   n1 [label=\"3\", style=filled, fillcolor=red, fontcolor=white];\n\
+             \x20   n0 -> n2;\n\
+             \x20   n2 [label=\"8\", style=filled, fillcolor=red, fontcolor=white];\n\
+             }\n"
+        );
+        let with_nil = rb_tree.to_dot_with(&DotOptions { show_nil: true });
+        assert_eq!(with_nil.matches("label=\"NIL\"").count(), 4);
+
+        let mut avl_tree: AVLTree<String> = AVLTree::new();
+        for word in ["b", "a", "say \"c\""] {
+            avl_tree.insert(word.to_string());
+        }
+        avl_tree.delete(&"a".to_string());
+        let dot = avl_tree.to_dot();
+        assert!(dot.contains("n0 [label=\"\\\"b\\\"\\nh=2 bf=-1\"];"));
+        assert!(dot.contains("n1 [label=\"\\\"say \\\\\\\"c\\\\\\\"\\\"\\nh=1 bf=0\"];"));
+    }
+}
@@ -0,0 +1,23 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+
+/// Internal failure while restructuring a tree. Each variant names a link
+/// the algorithm expected to find; hitting one means the tree was already
+/// corrupted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TreeError {
+    MissingChild,
+    MissingSibling,
+}
+
+impl Display for TreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            TreeError::MissingChild => "node is missing an expected child",
+            TreeError::MissingSibling => "node has no sibling",
+        };
+        f.write_str(description)
+    }
+}
+
+impl Error for TreeError {}
@@ -0,0 +1,147 @@
+use std::cmp::max;
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+
+use crate::tree::Tree;
+
+/// Tree property that a `validate` call found broken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    /// Keys are not in strictly ascending in-order sequence.
+    KeyOrder,
+    /// Cached subtree size differs from the number of nodes below.
+    StoredSize,
+    /// Cached total differs from the sum of the counts below the node.
+    StoredTotal,
+    /// Cached height differs from the real height of the subtree.
+    StoredHeight,
+    /// Cached leaf count differs from the real number of leaves.
+    StoredLeaves,
+    /// Cached black height differs from the one counted below the node.
+    StoredBlackHeight,
+    /// The root of a red-black tree is red.
+    RedRoot,
+    /// A red node has a red parent.
+    RedRedEdge,
+    /// The paths below a node hold different numbers of black nodes.
+    BlackHeight,
+    /// A child's `parent` link does not point back at its parent.
+    ParentLink,
+    /// Subtree heights below an AVL node differ by more than one.
+    Balance,
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            Rule::KeyOrder => "keys out of order",
+            Rule::StoredSize => "stored size is wrong",
+            Rule::StoredTotal => "stored total count is wrong",
+            Rule::StoredHeight => "stored height is wrong",
+            Rule::StoredLeaves => "stored leaf count is wrong",
+            Rule::StoredBlackHeight => "stored black height is wrong",
+            Rule::RedRoot => "root is red",
+            Rule::RedRedEdge => "red node has a red parent",
+            Rule::BlackHeight => "black height differs between paths",
+            Rule::ParentLink => "parent link is inconsistent",
+            Rule::Balance => "balance factor exceeds 1",
+        };
+        f.write_str(description)
+    }
+}
+
+/// Returned by `validate`: the key of the first offending node and the rule
+/// it breaks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvariantViolation<K> {
+    pub key: K,
+    pub rule: Rule,
+}
+
+impl<K> InvariantViolation<K> {
+    pub fn new(key: K, rule: Rule) -> Self {
+        InvariantViolation { key, rule }
+    }
+}
+
+impl<K: Debug> Display for InvariantViolation<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "key {:?}: {}", self.key, self.rule)
+    }
+}
+
+impl<K: Debug> Error for InvariantViolation<K> {}
+
+/// Checks the rules every tree shares: keys in order and cached sizes,
+/// heights and leaf counts that match the actual subtrees.
+pub fn validate_shape<K, T>(tree: &T) -> Result<(), InvariantViolation<K>>
+where
+    K: Ord + Clone,
+    T: Tree<K>,
+{
+    let mut previous: Option<K> = None;
+    for key in tree.iter() {
+        if previous.as_ref().is_some_and(|previous| *previous >= key) {
+            return Err(InvariantViolation::new(key, Rule::KeyOrder));
+        }
+        previous = Some(key);
+    }
+    if let Some(root) = tree.get_root() {
+        _validate_cached(tree, &root)?;
+    }
+    Ok(())
+}
+
+/// Checks that the subtree heights below every node differ by at most one.
+/// Relies on the cached heights, so run `validate_shape` first.
+pub fn validate_balance<K, T>(tree: &T) -> Result<(), InvariantViolation<K>>
+where
+    K: Ord + Clone,
+    T: Tree<K>,
+{
+    let mut stack: Vec<T::NodeRef> = tree.get_root().into_iter().collect();
+    while let Some(node) = stack.pop() {
+        let (left, right) = (tree.get_left(&node), tree.get_right(&node));
+        let height = |child: &Option<T::NodeRef>| child.as_ref().map_or(0, |child| tree.get_node_height(child));
+        if height(&left).abs_diff(height(&right)) > 1 {
+            return Err(InvariantViolation::new(tree.get_key(&node), Rule::Balance));
+        }
+        stack.extend(left.into_iter().chain(right));
+    }
+    Ok(())
+}
+
+/// Returns the real size, height and leaf count of the subtree at `node`.
+/// Totals are checked against the children's cached totals.
+fn _validate_cached<K, T>(tree: &T, node: &T::NodeRef) -> Result<(usize, u32, u32), InvariantViolation<K>>
+where
+    K: Ord + Clone,
+    T: Tree<K>,
+{
+    let mut size = 1;
+    let mut height = 0;
+    let mut leaves = 0;
+    let mut total = tree.get_node_count(node);
+    for child in [tree.get_left(node), tree.get_right(node)].iter().flatten() {
+        let (child_size, child_height, child_leaves) = _validate_cached(tree, child)?;
+        size += child_size;
+        height = max(height, child_height);
+        leaves += child_leaves;
+        total += tree.get_node_total(child);
+    }
+    let (height, leaves) = (height + 1, max(leaves, 1));
+
+    if tree.get_node_size(node) != size {
+        return Err(InvariantViolation::new(tree.get_key(node), Rule::StoredSize));
+    }
+    if tree.get_node_count(node) == 0 || tree.get_node_total(node) != total {
+        return Err(InvariantViolation::new(tree.get_key(node), Rule::StoredTotal));
+    }
+    if tree.get_node_height(node) != height {
+        return Err(InvariantViolation::new(tree.get_key(node), Rule::StoredHeight));
+    }
+    if tree.count_node_leaves(node) != leaves {
+        return Err(InvariantViolation::new(tree.get_key(node), Rule::StoredLeaves));
+    }
+    Ok((size, height, leaves))
+}
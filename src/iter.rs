@@ -0,0 +1,353 @@
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+
+use crate::tree::Tree;
+
+/// In-order iterator. The front stack holds the left spine still to be
+/// visited, the back stack the right spine; `remaining` stops the two ends
+/// once they meet.
+pub struct Iter<'a, K, T: Tree<K>>
+where
+    K: Ord + Clone,
+{
+    tree: &'a T,
+    front: Vec<T::NodeRef>,
+    back: Vec<T::NodeRef>,
+    remaining: usize,
+    _marker: PhantomData<K>,
+}
+
+impl<'a, K: Ord + Clone, T: Tree<K>> Iter<'a, K, T> {
+    pub(crate) fn new(tree: &'a T) -> Self {
+        let mut iter = Iter {
+            tree,
+            front: Vec::new(),
+            back: Vec::new(),
+            remaining: tree.len(),
+            _marker: PhantomData,
+        };
+        iter._push_left_spine(tree.get_root());
+        iter._push_right_spine(tree.get_root());
+        iter
+    }
+
+    fn _push_left_spine(&mut self, mut node: Option<T::NodeRef>) {
+        while let Some(current) = node {
+            node = self.tree.get_left(&current);
+            self.front.push(current);
+        }
+    }
+
+    fn _push_right_spine(&mut self, mut node: Option<T::NodeRef>) {
+        while let Some(current) = node {
+            node = self.tree.get_right(&current);
+            self.back.push(current);
+        }
+    }
+
+    /// Advances like `next` but hands out the node itself, for callers that
+    /// read more than the key.
+    pub(crate) fn next_node(&mut self) -> Option<T::NodeRef> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.front.pop()?;
+        self.remaining -= 1;
+        self._push_left_spine(self.tree.get_right(&node));
+        Some(node)
+    }
+
+    pub(crate) fn next_back_node(&mut self) -> Option<T::NodeRef> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.back.pop()?;
+        self.remaining -= 1;
+        self._push_right_spine(self.tree.get_left(&node));
+        Some(node)
+    }
+}
+
+impl<K: Ord + Clone, T: Tree<K>> Iterator for Iter<'_, K, T> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let node = self.next_node()?;
+        Some(self.tree.get_key(&node))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K: Ord + Clone, T: Tree<K>> DoubleEndedIterator for Iter<'_, K, T> {
+    fn next_back(&mut self) -> Option<K> {
+        let node = self.next_back_node()?;
+        Some(self.tree.get_key(&node))
+    }
+}
+
+impl<K: Ord + Clone, T: Tree<K>> ExactSizeIterator for Iter<'_, K, T> {}
+
+pub struct PreOrderIter<'a, K, T: Tree<K>>
+where
+    K: Ord + Clone,
+{
+    tree: &'a T,
+    stack: Vec<T::NodeRef>,
+    _marker: PhantomData<K>,
+}
+
+impl<'a, K: Ord + Clone, T: Tree<K>> PreOrderIter<'a, K, T> {
+    pub(crate) fn new(tree: &'a T) -> Self {
+        PreOrderIter {
+            tree,
+            stack: tree.get_root().into_iter().collect(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K: Ord + Clone, T: Tree<K>> Iterator for PreOrderIter<'_, K, T> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let curr = self.stack.pop()?;
+        if let Some(right) = self.tree.get_right(&curr) {
+            self.stack.push(right);
+        }
+        if let Some(left) = self.tree.get_left(&curr) {
+            self.stack.push(left);
+        }
+        Some(self.tree.get_key(&curr))
+    }
+}
+
+/// Post-order iterator. Each node is pushed twice: first to expand its
+/// children, then (marked as expanded) to be yielded after them.
+pub struct PostOrderIter<'a, K, T: Tree<K>>
+where
+    K: Ord + Clone,
+{
+    tree: &'a T,
+    stack: Vec<(T::NodeRef, bool)>,
+    _marker: PhantomData<K>,
+}
+
+impl<'a, K: Ord + Clone, T: Tree<K>> PostOrderIter<'a, K, T> {
+    pub(crate) fn new(tree: &'a T) -> Self {
+        PostOrderIter {
+            tree,
+            stack: tree.get_root().into_iter().map(|node| (node, false)).collect(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K: Ord + Clone, T: Tree<K>> Iterator for PostOrderIter<'_, K, T> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        while let Some((curr, expanded)) = self.stack.pop() {
+            if expanded {
+                return Some(self.tree.get_key(&curr));
+            }
+            let (left, right) = (self.tree.get_left(&curr), self.tree.get_right(&curr));
+            self.stack.push((curr, true));
+            if let Some(right) = right {
+                self.stack.push((right, false));
+            }
+            if let Some(left) = left {
+                self.stack.push((left, false));
+            }
+        }
+        None
+    }
+}
+
+pub struct LevelOrderIter<'a, K, T: Tree<K>>
+where
+    K: Ord + Clone,
+{
+    tree: &'a T,
+    queue: VecDeque<T::NodeRef>,
+    _marker: PhantomData<K>,
+}
+
+impl<'a, K: Ord + Clone, T: Tree<K>> LevelOrderIter<'a, K, T> {
+    pub(crate) fn new(tree: &'a T) -> Self {
+        LevelOrderIter {
+            tree,
+            queue: tree.get_root().into_iter().collect(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K: Ord + Clone, T: Tree<K>> Iterator for LevelOrderIter<'_, K, T> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let curr = self.queue.pop_front()?;
+        if let Some(left) = self.tree.get_left(&curr) {
+            self.queue.push_back(left);
+        }
+        if let Some(right) = self.tree.get_right(&curr) {
+            self.queue.push_back(right);
+        }
+        Some(self.tree.get_key(&curr))
+    }
+}
+
+
+/// In-order iterator over the keys inside `range`. The stack starts as the
+/// path to the first key at or after the lower bound, so only the keys in
+/// the range (plus one path) are ever visited.
+pub struct Range<'a, K, T: Tree<K>, R>
+where
+    K: Ord + Clone,
+{
+    tree: &'a T,
+    stack: Vec<T::NodeRef>,
+    range: R,
+    _marker: PhantomData<K>,
+}
+
+impl<'a, K: Ord + Clone, T: Tree<K>, R: RangeBounds<K>> Range<'a, K, T, R> {
+    pub(crate) fn new(tree: &'a T, range: R) -> Self {
+        let mut stack = Vec::new();
+        let mut node = tree.get_root();
+        while let Some(current) = node {
+            let after_start = tree.with_key(&current, |key| match range.start_bound() {
+                Bound::Included(start) => key >= start,
+                Bound::Excluded(start) => key > start,
+                Bound::Unbounded => true,
+            });
+            if after_start {
+                node = tree.get_left(&current);
+                stack.push(current);
+            } else {
+                node = tree.get_right(&current);
+            }
+        }
+        Range {
+            tree,
+            stack,
+            range,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K: Ord + Clone, T: Tree<K>, R: RangeBounds<K>> Iterator for Range<'_, K, T, R> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let node = self.stack.pop()?;
+        let key = self.tree.get_key(&node);
+        let before_end = match self.range.end_bound() {
+            Bound::Included(end) => key <= *end,
+            Bound::Excluded(end) => key < *end,
+            Bound::Unbounded => true,
+        };
+        if !before_end {
+            self.stack.clear();
+            return None;
+        }
+
+        let mut next = self.tree.get_right(&node);
+        while let Some(current) = next {
+            next = self.tree.get_left(&current);
+            self.stack.push(current);
+        }
+        Some(key)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::Bound;
+
+    use crate::avltree::AVLTree;
+    use crate::rbtree::RBTree;
+    use crate::tree::Tree;
+
+    #[test]
+    fn test_traversal_orders() {
+        let mut avl_tree: AVLTree<i64> = AVLTree::new();
+        for number in [4, 2, 6, 1, 3, 5, 7] {
+            avl_tree.insert(number);
+        }
+
+        assert_eq!(avl_tree.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(avl_tree.iter().rev().collect::<Vec<_>>(), vec![7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(avl_tree.iter_pre_order().collect::<Vec<_>>(), vec![4, 2, 1, 3, 6, 5, 7]);
+        assert_eq!(avl_tree.iter_post_order().collect::<Vec<_>>(), vec![1, 3, 2, 5, 7, 6, 4]);
+        assert_eq!(avl_tree.iter_level_order().collect::<Vec<_>>(), vec![4, 2, 6, 1, 3, 5, 7]);
+    }
+
+    #[test]
+    fn test_double_ended_iter() {
+        let mut rb_tree: RBTree<i64> = RBTree::new();
+        assert_eq!(rb_tree.iter().next(), None);
+        assert_eq!(rb_tree.iter().next_back(), None);
+
+        for number in 1..=20 {
+            rb_tree.insert(number);
+        }
+        let mut iter = rb_tree.iter();
+        let mut seen = Vec::new();
+        loop {
+            match (iter.next(), iter.next_back()) {
+                (Some(front), Some(back)) => {
+                    seen.push(front);
+                    seen.push(back);
+                }
+                (Some(front), None) => seen.push(front),
+                (None, _) => break,
+            }
+        }
+        seen.sort();
+        assert_eq!(seen, (1..=20).collect::<Vec<_>>());
+        assert_eq!(iter.next(), None);
+
+        let mut sum = 0;
+        for number in &rb_tree {
+            sum += number;
+        }
+        assert_eq!(sum, 210);
+    }
+
+    #[test]
+    fn test_range() {
+        let mut rb_tree: RBTree<i64> = RBTree::new();
+        let mut avl_tree: AVLTree<i64> = AVLTree::new();
+        for number in (0..100).step_by(3) {
+            rb_tree.insert(number);
+            avl_tree.insert(number);
+        }
+        let expected = |low: i64, high: i64| (low..high).filter(|n| n % 3 == 0).collect::<Vec<_>>();
+
+        for tree_range in [rb_tree.range(10..40).collect::<Vec<_>>(), avl_tree.range(10..40).collect()] {
+            assert_eq!(tree_range, expected(10, 40));
+        }
+        for tree_range in [rb_tree.range(9..=39).collect::<Vec<_>>(), avl_tree.range(9..=39).collect()] {
+            assert_eq!(tree_range, expected(9, 40));
+        }
+        for tree_range in [rb_tree.range(..12).collect::<Vec<_>>(), avl_tree.range(..12).collect()] {
+            assert_eq!(tree_range, expected(0, 12));
+        }
+        for tree_range in [rb_tree.range(90..).collect::<Vec<_>>(), avl_tree.range(90..).collect()] {
+            assert_eq!(tree_range, expected(90, 100));
+        }
+
+        let bounds = (Bound::Excluded(9), Bound::Excluded(21));
+        assert_eq!(rb_tree.range(bounds).collect::<Vec<_>>(), vec![12, 15, 18]);
+        assert_eq!(avl_tree.range(bounds).collect::<Vec<_>>(), vec![12, 15, 18]);
+        assert_eq!(rb_tree.range(..).count(), 34);
+        assert_eq!(avl_tree.range(40..40).count(), 0);
+        assert_eq!(RBTree::<i64>::new().range(1..5).count(), 0);
+    }
+}
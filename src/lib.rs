@@ -1,6 +1,23 @@
 pub mod avltree;
+pub mod iter;
 pub mod node;
 pub mod rbtree;
 pub mod tree;
 pub mod rbnode;
 pub mod avlnode;
+pub mod treemap;
+pub mod arena;
+pub mod arenarbtree;
+pub mod arenaavltree;
+pub mod invariant;
+pub mod error;
+pub mod dot;
+pub mod render;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod snapshot;
+pub mod setops;
+pub mod multiset;
+pub mod persistentrbtree;
+pub mod concurrent;
+pub mod cursor;
@@ -1,12 +1,11 @@
+use std::fs;
 use std::io;
-use tree::avlnode::AVLNode;
 use tree::avltree::AVLTree;
+use tree::dot::DotOptions;
 use tree::rbtree::RBTree;
 use tree::tree::Tree;
-use tree::node::Node;
-use tree::rbnode::RBNode;
 
-fn run<TN: Node, T: Tree<TN>> (mut tree: T) {
+fn run<T: Tree<i64>> (mut tree: T) {
     loop {
         println!("Please select an operation.");
         println!("1 - Insert a node to the tree.");
@@ -22,21 +21,27 @@ fn run<TN: Node, T: Tree<TN>> (mut tree: T) {
         println!("11 - Return the min element of the tree.");
         println!("12 - Search the tree for the given key.");
         println!("13 - Count the number of nodes.");
-        println!("14 - Exit");
+        println!("14 - Write the tree to a Graphviz DOT file.");
+        println!("15 - Exit");
         let operation = get_number_from_stdin();
 
         match operation {
             1 => {
                 println!("Please enter the key to insert");
                 let key = get_number_from_stdin();
-                tree.insert(key);
-                println!("Inserted {}", key);
+                if tree.insert(key) {
+                    println!("Inserted {}", key);
+                } else {
+                    println!("Key {} is already in the tree", key);
+                }
             }
             2 => {
                 println!("Please enter the key to delete");
                 let key = get_number_from_stdin();
-                tree.delete(key);
-                println!("Deleted {}", key);
+                match tree.delete(&key) {
+                    Some(deleted) => println!("Deleted {}", deleted),
+                    None => println!("Key {} not found", key),
+                }
             }
             3 => {
                 println!("Number of leaves: {}", tree.count_leaves());
@@ -72,12 +77,22 @@ fn run<TN: Node, T: Tree<TN>> (mut tree: T) {
             12 => {
                 println!("Please enter the key to search");
                 let key = get_number_from_stdin();
-                println!("Existance of the key in tree: {}", tree.contain(key));
+                println!("Existance of the key in tree: {}", tree.contain(&key));
             }
             13 => {
                 println!("Number of nodes in tree: {}", tree.count_nodes());
             }
             14 => {
+                println!("Please enter the file to write");
+                let path = get_line_from_stdin();
+                println!("Show NIL leaves? (y/n)");
+                let show_nil = get_line_from_stdin().eq_ignore_ascii_case("y");
+                match fs::write(&path, tree.to_dot_with(&DotOptions { show_nil })) {
+                    Ok(()) => println!("Wrote {}", path),
+                    Err(error) => println!("Could not write {}: {}", path, error),
+                }
+            }
+            15 => {
                 println!("Exiting...");
                 break;
             }
@@ -87,6 +102,15 @@ fn run<TN: Node, T: Tree<TN>> (mut tree: T) {
 }
 
 
+fn get_line_from_stdin() -> String {
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read line");
+    input.trim().to_string()
+}
+
+
 fn get_number_from_stdin() -> i64 {
     loop {
         let mut input = String::new();
@@ -118,13 +142,13 @@ fn main() {
             println!("RB tree is selected!");
 
             let tree = RBTree::new();
-            run::<RBNode, RBTree>(tree);
+            run::<RBTree<i64>>(tree);
         },
         2 => {
             println!("AVL tree is selected!");
             let tree = AVLTree::new();
 
-            run::<AVLNode, AVLTree>(tree);
+            run::<AVLTree<i64>>(tree);
         },
         _ => println!("Invalid tree type"),
     }
@@ -0,0 +1,302 @@
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use crate::avlnode::AVLNode;
+use crate::avltree::AVLTree;
+use crate::rbnode::RBNode;
+use crate::rbtree::RBTree;
+use crate::tree::Tree;
+
+/// Trees whose nodes can hold more than one copy of their key.
+pub trait CountedTree<K: Ord + Clone>: Tree<K> {
+    /// Adds `delta` to the count of the last node of `path`, which runs from
+    /// the root, and to the cached totals of every node on it.
+    fn add_count(&mut self, path: &[Self::NodeRef], delta: isize);
+}
+
+impl<K: Ord + Clone + Debug> CountedTree<K> for RBTree<K> {
+    fn add_count(&mut self, path: &[Self::NodeRef], delta: isize) {
+        RBNode::add_count(path, delta);
+    }
+}
+
+impl<K: Ord + Clone + Debug> CountedTree<K> for AVLTree<K> {
+    fn add_count(&mut self, path: &[Self::NodeRef], delta: isize) {
+        AVLNode::add_count(path, delta);
+    }
+}
+
+pub type RBMultiset<K> = Multiset<K, RBTree<K>>;
+pub type AVLMultiset<K> = Multiset<K, AVLTree<K>>;
+
+/// Ordered multiset: one node per distinct key, holding how many copies of
+/// it were inserted. Order statistics count every copy.
+pub struct Multiset<K, T> {
+    tree: T,
+    _marker: PhantomData<K>,
+}
+
+impl<K, T> Multiset<K, T>
+where
+    K: Ord + Clone + Debug,
+    T: CountedTree<K>,
+{
+    pub fn new() -> Self {
+        Multiset {
+            tree: T::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Number of copies, counting duplicates.
+    pub fn len(&self) -> usize {
+        self.tree.get_root().map_or(0, |root| self.tree.get_node_total(&root))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Number of distinct keys.
+    pub fn distinct_len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Adds one copy of `key` and returns how many there are now.
+    pub fn insert(&mut self, key: K) -> usize {
+        let (exists, path) = self.tree.search_path(&key);
+        if !exists {
+            self.tree.insert_at(path, key);
+            return 1;
+        }
+        self.tree.add_count(&path, 1);
+        self.tree.get_node_count(path.last().unwrap())
+    }
+
+    /// Removes one copy of `key`. Returns false if there was none.
+    pub fn remove_one<Q>(&mut self, key: &Q) -> bool
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (exists, path) = self.tree.search_path(key);
+        if !exists {
+            return false;
+        }
+        if self.tree.get_node_count(path.last().unwrap()) == 1 {
+            self.tree.delete_at(path);
+        } else {
+            self.tree.add_count(&path, -1);
+        }
+        true
+    }
+
+    /// Removes every copy of `key` and returns how many there were.
+    pub fn remove_all<Q>(&mut self, key: &Q) -> usize
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (exists, path) = self.tree.search_path(key);
+        if !exists {
+            return 0;
+        }
+        let count = self.tree.get_node_count(path.last().unwrap());
+        self.tree.delete_at(path);
+        count
+    }
+
+    pub fn count<Q>(&self, key: &Q) -> usize
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match self.tree.search(key) {
+            (true, Some(node)) => self.tree.get_node_count(&node),
+            _ => 0,
+        }
+    }
+
+    /// The key at `index` in sorted order with every copy listed, so a key
+    /// inserted twice occupies two indices.
+    pub fn select(&self, mut index: usize) -> Option<K> {
+        let mut current = self.tree.get_root();
+        while let Some(node) = current {
+            let left = self.tree.get_left(&node);
+            let left_total = left.as_ref().map_or(0, |left| self.tree.get_node_total(left));
+            let count = self.tree.get_node_count(&node);
+
+            current = if index < left_total {
+                left
+            } else if index < left_total + count {
+                return Some(self.tree.get_key(&node));
+            } else {
+                index -= left_total + count;
+                self.tree.get_right(&node)
+            };
+        }
+        None
+    }
+
+    /// Number of copies strictly less than `key`.
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut rank = 0;
+        let mut current = self.tree.get_root();
+        while let Some(node) = current {
+            let left = self.tree.get_left(&node);
+            let left_total = left.as_ref().map_or(0, |left| self.tree.get_node_total(left));
+
+            current = match self.tree.with_key(&node, |node_key| node_key.borrow().cmp(key)) {
+                Ordering::Equal => return rank + left_total,
+                Ordering::Greater => left,
+                Ordering::Less => {
+                    rank += left_total + self.tree.get_node_count(&node);
+                    self.tree.get_right(&node)
+                }
+            };
+        }
+        rank
+    }
+
+    /// Distinct keys in ascending order, each with its count.
+    pub fn iter(&self) -> impl Iterator<Item = (K, usize)> + '_ {
+        let mut nodes = self.tree.iter();
+        std::iter::from_fn(move || {
+            let node = nodes.next_node()?;
+            Some((self.tree.get_key(&node), self.tree.get_node_count(&node)))
+        })
+    }
+
+    /// The tree underneath, with one node per distinct key.
+    pub fn tree(&self) -> &T {
+        &self.tree
+    }
+}
+
+impl<K, T> Default for Multiset<K, T>
+where
+    K: Ord + Clone + Debug,
+    T: CountedTree<K>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, T> Extend<K> for Multiset<K, T>
+where
+    K: Ord + Clone + Debug,
+    T: CountedTree<K>,
+{
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+impl<K, T> FromIterator<K> for Multiset<K, T>
+where
+    K: Ord + Clone + Debug,
+    T: CountedTree<K>,
+{
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut multiset = Self::new();
+        multiset.extend(iter);
+        multiset
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use proptest::prelude::*;
+
+    use crate::multiset::{AVLMultiset, CountedTree, Multiset, RBMultiset};
+
+    fn check_histogram<T: CountedTree<i64>>(mut multiset: Multiset<i64, T>) {
+        assert!(multiset.is_empty());
+        for number in [5, 3, 5, 8, 1, 5, 3] {
+            multiset.insert(number);
+        }
+        assert_eq!(multiset.len(), 7);
+        assert_eq!(multiset.distinct_len(), 4);
+        assert_eq!(multiset.count(&5), 3);
+        assert_eq!(multiset.count(&4), 0);
+        assert_eq!(multiset.iter().collect::<Vec<_>>(), vec![(1, 1), (3, 2), (5, 3), (8, 1)]);
+        let selected: Vec<_> = (0..8).map(|index| multiset.select(index)).collect();
+        assert_eq!(selected, [1, 3, 3, 5, 5, 5, 8].map(Some).into_iter().chain([None]).collect::<Vec<_>>());
+        assert_eq!(multiset.rank(&5), 3);
+        assert_eq!(multiset.rank(&6), 6);
+
+        assert!(multiset.remove_one(&5));
+        assert!(!multiset.remove_one(&4));
+        assert_eq!(multiset.count(&5), 2);
+        assert_eq!(multiset.remove_all(&3), 2);
+        assert_eq!(multiset.remove_all(&3), 0);
+        assert_eq!(multiset.len(), 4);
+        assert_eq!(multiset.rank(&8), 3);
+    }
+
+    #[test]
+    fn test_multiset() {
+        check_histogram(RBMultiset::new());
+        check_histogram(AVLMultiset::new());
+    }
+
+    proptest! {
+        #[test]
+        fn test_multiset_matches_model(operations in prop::collection::vec((0u8..3, 0i64..40), 0..300)) {
+            let mut rb_multiset: RBMultiset<i64> = RBMultiset::new();
+            let mut avl_multiset: AVLMultiset<i64> = AVLMultiset::new();
+            let mut model: BTreeMap<i64, usize> = BTreeMap::new();
+            for (operation, key) in operations {
+                match operation {
+                    0 => {
+                        rb_multiset.insert(key);
+                        avl_multiset.insert(key);
+                        *model.entry(key).or_default() += 1;
+                    }
+                    1 => {
+                        let expected = model.contains_key(&key);
+                        prop_assert_eq!(rb_multiset.remove_one(&key), expected);
+                        prop_assert_eq!(avl_multiset.remove_one(&key), expected);
+                        if let Some(count) = model.get_mut(&key) {
+                            *count -= 1;
+                            if *count == 0 {
+                                model.remove(&key);
+                            }
+                        }
+                    }
+                    _ => {
+                        let expected = model.remove(&key).unwrap_or(0);
+                        prop_assert_eq!(rb_multiset.remove_all(&key), expected);
+                        prop_assert_eq!(avl_multiset.remove_all(&key), expected);
+                    }
+                }
+                rb_multiset.tree().validate().unwrap();
+                avl_multiset.tree().validate().unwrap();
+            }
+
+            let expanded: Vec<i64> = model.iter().flat_map(|(key, count)| std::iter::repeat_n(*key, *count)).collect();
+            for multiset_len in [rb_multiset.len(), avl_multiset.len()] {
+                prop_assert_eq!(multiset_len, expanded.len());
+            }
+            for (index, key) in expanded.iter().enumerate() {
+                prop_assert_eq!(rb_multiset.select(index), Some(*key));
+                prop_assert_eq!(avl_multiset.select(index), Some(*key));
+                let rank = expanded.partition_point(|other| other < key);
+                prop_assert_eq!(rb_multiset.rank(key), rank);
+                prop_assert_eq!(avl_multiset.rank(key), rank);
+            }
+            prop_assert_eq!(rb_multiset.iter().collect::<Vec<_>>(), model.into_iter().collect::<Vec<_>>());
+        }
+    }
+}
@@ -1,17 +1,37 @@
 use std::cell::RefCell;
 use std::cmp::{max, Ordering};
+use std::fmt;
 use std::rc::Rc;
 
-pub trait Node {
-    fn new(key: i64) -> Option<Rc<RefCell<Self>>>;
+pub trait Node<K: Ord + Clone> {
+    fn new(key: K) -> Option<Rc<RefCell<Self>>>;
 
-    fn print_node(&self, prefix_space: &String, child_prefix: String, is_right: bool);
+    fn render_node(&self, out: &mut dyn fmt::Write, prefix_space: &str, child_prefix: String, is_right: bool) -> fmt::Result;
+
+    fn print_node(&self, prefix_space: &str, child_prefix: String, is_right: bool) {
+        let mut out = String::new();
+        self.render_node(&mut out, prefix_space, child_prefix, is_right).unwrap();
+        print!("{}", out);
+    }
 
     fn get_left(&self) -> &Option<Rc<RefCell<Self>>>;
 
     fn get_right(&self) -> &Option<Rc<RefCell<Self>>>;
 
-    fn get_key(&self) -> i64;
+    fn get_key(&self) -> &K;
+
+    /// Number of nodes in the subtree rooted here, kept up to date by the tree.
+    fn get_size(&self) -> usize;
+
+    /// Copies of the key this node holds; more than one only in a multiset.
+    fn get_count(&self) -> usize {
+        1
+    }
+
+    /// Sum of the counts in the subtree rooted here.
+    fn get_total(&self) -> usize {
+        self.get_size()
+    }
 
     fn get_height(&self) -> u32 {
         match (self.get_left(), self.get_right()) {
@@ -21,33 +41,33 @@ pub trait Node {
             (_, _) => 1,
         }
     }
-    
 
-    fn get_min(&self) -> i64 {
-        self.get_left().as_ref().map_or_else(|| self.get_key(), |left| left.borrow().get_min())
+
+    fn get_min(&self) -> K {
+        self.get_left().as_ref().map_or_else(|| self.get_key().clone(), |left| left.borrow().get_min())
     }
-    
-    fn get_max(&self) -> i64 {
-        self.get_right().as_ref().map_or_else(|| self.get_key(), |right| right.borrow().get_max())
+
+    fn get_max(&self) -> K {
+        self.get_right().as_ref().map_or_else(|| self.get_key().clone(), |right| right.borrow().get_max())
     }
-    
+
 
     fn count_leaves(&self) -> u32 {
         if let (Some(left), Some(right)) = (self.get_left(), self.get_right()) {
             return left.borrow().count_leaves() + right.borrow().count_leaves();
         }
-    
+
         if let Some(left) = self.get_left() {
             return left.borrow().count_leaves();
         }
-    
+
         if let Some(right) = self.get_right() {
             return right.borrow().count_leaves();
         }
-    
+
         1
     }
-    
+
 
     fn count_nodes(&self) -> u32 {
         match (self.get_left(), self.get_right()) {
@@ -57,10 +77,14 @@ pub trait Node {
             (_, _) => 1,
         }
     }
-    
 
-    fn contains(&self, key: i64) -> bool {
-        match self.get_key().cmp(&key) {
+
+    fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match self.get_key().borrow().cmp(key) {
             Ordering::Less => match self.get_right() {
                 None => false,
                 Some(right) => right.borrow().contains(key),
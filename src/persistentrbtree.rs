@@ -0,0 +1,508 @@
+use std::cmp::{max, Ordering};
+use std::fmt::{self, Debug, Display};
+use std::rc::Rc;
+
+use crate::dot::{color_style, DotNodeStyle};
+use crate::invariant::{validate_shape, InvariantViolation, Rule};
+use crate::iter::Iter;
+use crate::rbnode::NodeColor;
+use crate::render::{color_label, RenderStyle};
+use crate::tree::Tree;
+
+pub type RcPersistentNode<K> = Rc<PersistentNode<K>>;
+type OptionNode<K> = Option<RcPersistentNode<K>>;
+
+/// Node of a `PersistentRBTree`. Never changed once built, so any number of
+/// tree versions can share it.
+#[derive(Debug)]
+pub struct PersistentNode<K> {
+    pub key: K,
+    pub color: NodeColor,
+    left: OptionNode<K>,
+    right: OptionNode<K>,
+    size: usize,
+    height: u32,
+    leaves: u32,
+}
+
+/// Red-black tree with path copying: an update rebuilds only the nodes on
+/// the path it walks, O(log n) of them, and shares every other subtree with
+/// the previous version. Cloning is O(1) and gives a snapshot that later
+/// updates do not affect.
+#[derive(Clone)]
+pub struct PersistentRBTree<K> {
+    _root: OptionNode<K>,
+}
+
+impl<K: Ord + Clone + Debug> Tree<K> for PersistentRBTree<K> {
+    type NodeRef = RcPersistentNode<K>;
+
+    fn new() -> Self {
+        PersistentRBTree { _root: None }
+    }
+
+    fn get_root(&self) -> Option<RcPersistentNode<K>> {
+        self._root.clone()
+    }
+
+    fn get_left(&self, node: &RcPersistentNode<K>) -> Option<RcPersistentNode<K>> {
+        node.left.clone()
+    }
+
+    fn get_right(&self, node: &RcPersistentNode<K>) -> Option<RcPersistentNode<K>> {
+        node.right.clone()
+    }
+
+    fn with_key<R>(&self, node: &RcPersistentNode<K>, f: impl FnOnce(&K) -> R) -> R {
+        f(&node.key)
+    }
+
+    fn get_node_size(&self, node: &RcPersistentNode<K>) -> usize {
+        node.size
+    }
+
+    fn get_node_height(&self, node: &RcPersistentNode<K>) -> u32 {
+        node.height
+    }
+
+    fn count_node_leaves(&self, node: &RcPersistentNode<K>) -> u32 {
+        node.leaves
+    }
+
+    /// Replaces this handle with the next version; clones taken before keep
+    /// seeing the old one.
+    fn insert(&mut self, key: K) -> bool {
+        if self.contain(&key) {
+            return false;
+        }
+        self._root = Some(Self::_blacken(Self::_insert(&self._root, key)));
+        true
+    }
+
+    fn delete<Q>(&mut self, key: &Q) -> Option<K>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (true, Some(node)) = self.search(key) else {
+            return None;
+        };
+        self._root = Self::_delete(&self._root, key).map(Self::_blacken);
+        Some(node.key.clone())
+    }
+
+    /// The path is not reused: every node on it is copied anyway.
+    fn insert_at(&mut self, _path: Vec<RcPersistentNode<K>>, key: K) {
+        self._root = Some(Self::_blacken(Self::_insert(&self._root, key)));
+    }
+
+    /// As with `insert_at`, only the last node of the path is read.
+    fn delete_at(&mut self, path: Vec<RcPersistentNode<K>>) -> K {
+        let key = path.last().unwrap().key.clone();
+        self._root = Self::_delete(&self._root, &key).map(Self::_blacken);
+        key
+    }
+
+    fn render_sideways(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        match &self._root {
+            Some(root) => Self::_render_node(out, root, "", "Root", false),
+            None => Ok(()),
+        }
+    }
+
+    fn dot_node_style(&self, node: &RcPersistentNode<K>) -> DotNodeStyle {
+        color_style(&node.color)
+    }
+
+    fn render_label(&self, node: &RcPersistentNode<K>) -> String {
+        color_label(&node.key, &node.color)
+    }
+}
+
+impl<K: Ord + Clone + Debug> PersistentRBTree<K> {
+    /// Returns a new version holding `key` as well, leaving `self` as it is.
+    #[must_use]
+    pub fn inserted(&self, key: K) -> Self {
+        let mut tree = self.clone();
+        tree.insert(key);
+        tree
+    }
+
+    /// Returns a new version without `key`, leaving `self` as it is.
+    #[must_use]
+    pub fn deleted<Q>(&self, key: &Q) -> Self
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut tree = self.clone();
+        tree.delete(key);
+        tree
+    }
+
+    /// Whether both trees are the same version, so comparing them is O(1).
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self._root, &other._root) {
+            (Some(root), Some(other_root)) => Rc::ptr_eq(root, other_root),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Checks the red-black rules and every cached field.
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>> {
+        validate_shape(self)?;
+        if let Some(root) = &self._root {
+            if root.color == NodeColor::Red {
+                return Err(InvariantViolation::new(root.key.clone(), Rule::RedRoot));
+            }
+            Self::_validate_node(root, false)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of black nodes below `node` down to a NIL leaf,
+    /// counting the leaf but not `node`.
+    fn _validate_node(node: &RcPersistentNode<K>, parent_is_red: bool) -> Result<u32, InvariantViolation<K>> {
+        let violation = |rule| Err(InvariantViolation::new(node.key.clone(), rule));
+        let is_red = node.color == NodeColor::Red;
+        if is_red && parent_is_red {
+            return violation(Rule::RedRedEdge);
+        }
+
+        let mut black_heights = [1, 1];
+        for (child, black_height) in [&node.left, &node.right].into_iter().zip(&mut black_heights) {
+            if let Some(child) = child {
+                let is_black = child.color == NodeColor::Black;
+                *black_height = Self::_validate_node(child, is_red)? + is_black as u32;
+            }
+        }
+        if black_heights[0] != black_heights[1] {
+            return violation(Rule::BlackHeight);
+        }
+        Ok(black_heights[0])
+    }
+
+    /// Builds a node and computes its cached fields from its children.
+    fn _make(color: NodeColor, left: OptionNode<K>, key: K, right: OptionNode<K>) -> RcPersistentNode<K> {
+        let (mut size, mut height, mut leaves) = (1, 0, 0);
+        for child in [&left, &right].into_iter().flatten() {
+            size += child.size;
+            height = max(height, child.height);
+            leaves += child.leaves;
+        }
+        Rc::new(PersistentNode {
+            key,
+            color,
+            left,
+            right,
+            size,
+            height: height + 1,
+            leaves: max(leaves, 1),
+        })
+    }
+
+    /// `node` with its color changed, copied only if the color differs.
+    fn _recolor(node: &RcPersistentNode<K>, color: NodeColor) -> RcPersistentNode<K> {
+        if node.color == color {
+            return node.clone();
+        }
+        Self::_make(color, node.left.clone(), node.key.clone(), node.right.clone())
+    }
+
+    fn _blacken(node: RcPersistentNode<K>) -> RcPersistentNode<K> {
+        Self::_recolor(&node, NodeColor::Black)
+    }
+
+    fn _is_red(node: &OptionNode<K>) -> bool {
+        node.as_ref().is_some_and(|node| node.color == NodeColor::Red)
+    }
+
+    fn _is_black(node: &OptionNode<K>) -> bool {
+        node.as_ref().is_some_and(|node| node.color == NodeColor::Black)
+    }
+
+    /// Okasaki's balance: a black node over `left` and `right`, unless one of
+    /// them is red with a red child, in which case the three keys involved
+    /// become a red node with two black children.
+    fn _balance(left: OptionNode<K>, key: K, right: OptionNode<K>) -> RcPersistentNode<K> {
+        use NodeColor::{Black, Red};
+        if let (Some(inner_left), Some(inner_right)) = (&left, &right) {
+            if inner_left.color == Red && inner_right.color == Red {
+                return Self::_make(Red, Some(Self::_blacken(inner_left.clone())), key, Some(Self::_blacken(inner_right.clone())));
+            }
+        }
+        if let Some(inner_left) = left.as_ref().filter(|left| left.color == Red) {
+            if let Some(outer) = inner_left.left.as_ref().filter(|node| node.color == Red) {
+                let new_left = Self::_make(Black, outer.left.clone(), outer.key.clone(), outer.right.clone());
+                let new_right = Self::_make(Black, inner_left.right.clone(), key, right);
+                return Self::_make(Red, Some(new_left), inner_left.key.clone(), Some(new_right));
+            }
+            if let Some(inner) = inner_left.right.as_ref().filter(|node| node.color == Red) {
+                let new_left = Self::_make(Black, inner_left.left.clone(), inner_left.key.clone(), inner.left.clone());
+                let new_right = Self::_make(Black, inner.right.clone(), key, right);
+                return Self::_make(Red, Some(new_left), inner.key.clone(), Some(new_right));
+            }
+        }
+        if let Some(inner_right) = right.as_ref().filter(|right| right.color == Red) {
+            if let Some(outer) = inner_right.right.as_ref().filter(|node| node.color == Red) {
+                let new_left = Self::_make(Black, left, key, inner_right.left.clone());
+                let new_right = Self::_make(Black, outer.left.clone(), outer.key.clone(), outer.right.clone());
+                return Self::_make(Red, Some(new_left), inner_right.key.clone(), Some(new_right));
+            }
+            if let Some(inner) = inner_right.left.as_ref().filter(|node| node.color == Red) {
+                let new_left = Self::_make(Black, left, key, inner.left.clone());
+                let new_right = Self::_make(Black, inner.right.clone(), inner_right.key.clone(), inner_right.right.clone());
+                return Self::_make(Red, Some(new_left), inner.key.clone(), Some(new_right));
+            }
+        }
+        Self::_make(Black, left, key, right)
+    }
+
+    /// Copies the path down to where `key` belongs and adds it there as a red
+    /// leaf. `key` must not be in the subtree yet.
+    fn _insert(node: &OptionNode<K>, key: K) -> RcPersistentNode<K> {
+        let Some(node) = node else {
+            return Self::_make(NodeColor::Red, None, key, None);
+        };
+        let is_less = key < node.key;
+        match (&node.color, is_less) {
+            (NodeColor::Black, true) => Self::_balance(Some(Self::_insert(&node.left, key)), node.key.clone(), node.right.clone()),
+            (NodeColor::Black, false) => Self::_balance(node.left.clone(), node.key.clone(), Some(Self::_insert(&node.right, key))),
+            (NodeColor::Red, true) => Self::_make(NodeColor::Red, Some(Self::_insert(&node.left, key)), node.key.clone(), node.right.clone()),
+            (NodeColor::Red, false) => Self::_make(NodeColor::Red, node.left.clone(), node.key.clone(), Some(Self::_insert(&node.right, key))),
+        }
+    }
+
+    /// Kahrs' functional delete. Removing from below a black node shortens
+    /// that side by one black node, which `_balance_left` and
+    /// `_balance_right` make up for. `key` must be in the subtree.
+    fn _delete<Q>(node: &OptionNode<K>, key: &Q) -> OptionNode<K>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let node = node.as_ref()?;
+        match node.key.borrow().cmp(key) {
+            Ordering::Greater => {
+                let left = Self::_delete(&node.left, key);
+                if Self::_is_black(&node.left) {
+                    Some(Self::_balance_left(left, node.key.clone(), node.right.clone()))
+                } else {
+                    Some(Self::_make(NodeColor::Red, left, node.key.clone(), node.right.clone()))
+                }
+            }
+            Ordering::Less => {
+                let right = Self::_delete(&node.right, key);
+                if Self::_is_black(&node.right) {
+                    Some(Self::_balance_right(node.left.clone(), node.key.clone(), right))
+                } else {
+                    Some(Self::_make(NodeColor::Red, node.left.clone(), node.key.clone(), right))
+                }
+            }
+            Ordering::Equal => Self::_fuse(&node.left, &node.right),
+        }
+    }
+
+    /// Joins `left`, one black node shorter than `right`, and `right` under
+    /// `key`.
+    fn _balance_left(left: OptionNode<K>, key: K, right: OptionNode<K>) -> RcPersistentNode<K> {
+        use NodeColor::{Black, Red};
+        if let Some(inner_left) = left.as_ref().filter(|left| left.color == Red) {
+            return Self::_make(Red, Some(Self::_blacken(inner_left.clone())), key, right);
+        }
+        let right = right.expect("PersistentRBTree: black heights differ");
+        if right.color == Black {
+            return Self::_balance(left, key, Some(Self::_recolor(&right, Red)));
+        }
+        let inner = right.left.as_ref().filter(|inner| inner.color == Black).expect("PersistentRBTree: red node without black children");
+        let new_left = Self::_make(Black, left, key, inner.left.clone());
+        let new_right = Self::_balance(inner.right.clone(), right.key.clone(), right.right.as_ref().map(|outer| Self::_recolor(outer, Red)));
+        Self::_make(Red, Some(new_left), inner.key.clone(), Some(new_right))
+    }
+
+    /// Mirror of `_balance_left`, for a `right` one black node shorter.
+    fn _balance_right(left: OptionNode<K>, key: K, right: OptionNode<K>) -> RcPersistentNode<K> {
+        use NodeColor::{Black, Red};
+        if let Some(inner_right) = right.as_ref().filter(|right| right.color == Red) {
+            return Self::_make(Red, left, key, Some(Self::_blacken(inner_right.clone())));
+        }
+        let left = left.expect("PersistentRBTree: black heights differ");
+        if left.color == Black {
+            return Self::_balance(Some(Self::_recolor(&left, Red)), key, right);
+        }
+        let inner = left.right.as_ref().filter(|inner| inner.color == Black).expect("PersistentRBTree: red node without black children");
+        let new_left = Self::_balance(left.left.as_ref().map(|outer| Self::_recolor(outer, Red)), left.key.clone(), inner.left.clone());
+        let new_right = Self::_make(Black, inner.right.clone(), key, right);
+        Self::_make(Red, Some(new_left), inner.key.clone(), Some(new_right))
+    }
+
+    /// Joins the two subtrees of a removed node, all of whose keys in `left`
+    /// are smaller than those in `right`.
+    fn _fuse(left: &OptionNode<K>, right: &OptionNode<K>) -> OptionNode<K> {
+        use NodeColor::{Black, Red};
+        let (Some(inner_left), Some(inner_right)) = (left, right) else {
+            return left.clone().or_else(|| right.clone());
+        };
+        match (&inner_left.color, &inner_right.color) {
+            (Red, Red) => {
+                let middle = Self::_fuse(&inner_left.right, &inner_right.left);
+                Some(match middle.as_ref().filter(|middle| middle.color == Red) {
+                    Some(middle) => Self::_make(
+                        Red,
+                        Some(Self::_make(Red, inner_left.left.clone(), inner_left.key.clone(), middle.left.clone())),
+                        middle.key.clone(),
+                        Some(Self::_make(Red, middle.right.clone(), inner_right.key.clone(), inner_right.right.clone())),
+                    ),
+                    None => Self::_make(
+                        Red,
+                        inner_left.left.clone(),
+                        inner_left.key.clone(),
+                        Some(Self::_make(Red, middle, inner_right.key.clone(), inner_right.right.clone())),
+                    ),
+                })
+            }
+            (Black, Black) => {
+                let middle = Self::_fuse(&inner_left.right, &inner_right.left);
+                Some(match middle.as_ref().filter(|middle| middle.color == Red) {
+                    Some(middle) => Self::_make(
+                        Red,
+                        Some(Self::_make(Black, inner_left.left.clone(), inner_left.key.clone(), middle.left.clone())),
+                        middle.key.clone(),
+                        Some(Self::_make(Black, middle.right.clone(), inner_right.key.clone(), inner_right.right.clone())),
+                    ),
+                    None => Self::_balance_left(
+                        inner_left.left.clone(),
+                        inner_left.key.clone(),
+                        Some(Self::_make(Black, middle, inner_right.key.clone(), inner_right.right.clone())),
+                    ),
+                })
+            }
+            (Black, Red) => Some(Self::_make(
+                Red,
+                Self::_fuse(left, &inner_right.left),
+                inner_right.key.clone(),
+                inner_right.right.clone(),
+            )),
+            (Red, Black) => Some(Self::_make(
+                Red,
+                inner_left.left.clone(),
+                inner_left.key.clone(),
+                Self::_fuse(&inner_left.right, right),
+            )),
+        }
+    }
+
+    fn _render_node(out: &mut dyn fmt::Write, node: &RcPersistentNode<K>, prefix_space: &str, child_prefix: &str, is_right: bool) -> fmt::Result {
+        if child_prefix == "Root" {
+            writeln!(out)?;
+        }
+        let color = if node.color == NodeColor::Black { "Black" } else { "Red" };
+
+        if let Some(left) = &node.left {
+            let mut new_prefix_space_left = String::from(prefix_space);
+            new_prefix_space_left.push_str(if is_right { "|     " } else { "      " });
+            Self::_render_node(out, left, &new_prefix_space_left, "L", false)?;
+        }
+        writeln!(out, "{}{} {:?} {}", prefix_space, child_prefix, node.key, color)?;
+        if let Some(right) = &node.right {
+            let mut new_prefix_space_right = String::from(prefix_space);
+            if is_right || child_prefix == "Root" {
+                new_prefix_space_right.push_str("      ");
+            } else {
+                new_prefix_space_right.push_str("|     ");
+            }
+            Self::_render_node(out, right, &new_prefix_space_right, "R", true)?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone + Debug> Display for PersistentRBTree<K> {
+    /// Draws the tree in `RenderStyle::Unicode`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render(f, RenderStyle::Unicode)
+    }
+}
+
+impl<K: Ord + Clone + Debug> FromIterator<K> for PersistentRBTree<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut tree = PersistentRBTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<K: Ord + Clone + Debug> Extend<K> for PersistentRBTree<K> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+impl<'a, K: Ord + Clone + Debug> IntoIterator for &'a PersistentRBTree<K> {
+    type Item = K;
+    type IntoIter = Iter<'a, K, PersistentRBTree<K>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::rc::Rc;
+
+    use crate::persistentrbtree::{PersistentRBTree, RcPersistentNode};
+    use crate::tree::Tree;
+
+    fn nodes(tree: &PersistentRBTree<i64>) -> HashSet<*const ()> {
+        let mut found = HashSet::new();
+        let mut stack: Vec<RcPersistentNode<i64>> = tree.get_root().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            found.insert(Rc::as_ptr(&node) as *const ());
+            stack.extend(tree.get_left(&node).into_iter().chain(tree.get_right(&node)));
+        }
+        found
+    }
+
+    #[test]
+    fn test_persistent_rbtree_versions() {
+        let mut versions = vec![PersistentRBTree::new()];
+        for number in [50, 20, 80, 10, 30, 70, 90, 25, 5, 1, 60, 65, 85, 95, 99] {
+            let next = versions.last().unwrap().inserted(number);
+            next.validate().unwrap();
+            versions.push(next);
+        }
+        for (index, version) in versions.iter().enumerate() {
+            assert_eq!(version.len(), index);
+        }
+        assert!(!versions[3].contain(&10) && versions[4].contain(&10));
+
+        let full = versions.last().unwrap().clone();
+        let mut expected = full.in_order_traversal();
+        let mut current = full.clone();
+        for number in [50, 1, 99, 25, 70, 20, 80, 65, 5, 10, 30, 60, 85, 90, 95] {
+            current = current.deleted(&number);
+            current.validate().unwrap();
+            expected.retain(|key| *key != number);
+            assert_eq!(current.in_order_traversal(), expected);
+        }
+        assert!(current.is_empty());
+        assert_eq!(full.len(), 15);
+        full.validate().unwrap();
+        assert!(full.ptr_eq(&full.deleted(&1000)));
+
+        let large: PersistentRBTree<i64> = (0..1000).collect();
+        let before = nodes(&large);
+        for updated in [large.inserted(5000), large.deleted(&500), large.deleted(&0)] {
+            updated.validate().unwrap();
+            let copied = nodes(&updated).difference(&before).count();
+            assert!(copied <= 3 * large.get_height() as usize, "copied {} nodes", copied);
+        }
+        assert_eq!(large.len(), 1000);
+    }
+}
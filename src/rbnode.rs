@@ -1,53 +1,119 @@
 use std::cell::RefCell;
-use std::fmt::Debug;
-use std::rc::Rc;
+use std::fmt::{self, Debug};
+use std::rc::{Rc, Weak};
 
 use crate::node::Node;
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NodeColor {
     Red,
     Black,
 }
 
-pub type RcRefcellRBTNode = Rc<RefCell<RBNode>>;
-pub type OptionNode = Option<RcRefcellRBTNode>;
+pub type RcRefcellRBTNode<K> = Rc<RefCell<RBNode<K>>>;
+pub type OptionNode<K> = Option<RcRefcellRBTNode<K>>;
+pub type WeakNode<K> = Option<Weak<RefCell<RBNode<K>>>>;
 
 #[derive(Debug)]
-pub struct RBNode {
-    pub key: i64,
+pub struct RBNode<K> {
+    pub key: K,
     pub color: NodeColor,
-    pub parent: OptionNode,
-    pub left: OptionNode,
-    pub right: OptionNode,
+    /// Weak so that a child and its parent do not keep each other alive.
+    pub parent: WeakNode<K>,
+    pub left: OptionNode<K>,
+    pub right: OptionNode<K>,
+    pub size: usize,
+    /// Copies of `key`, see `Multiset`.
+    pub count: usize,
+    /// Sum of `count` over the subtree.
+    pub total: usize,
+    pub height: u32,
+    /// Black nodes on a path down to a NIL leaf, counting the leaf but not
+    /// this node.
+    pub black_height: u32,
+    pub leaves: u32,
 }
 
-impl Node for RBNode {
-    fn new(key: i64) -> OptionNode {
+impl<K> RBNode<K> {
+    pub fn get_parent(&self) -> OptionNode<K> {
+        self.parent.as_ref().and_then(Weak::upgrade)
+    }
+
+    pub fn set_parent(&mut self, parent: &OptionNode<K>) {
+        self.parent = parent.as_ref().map(Rc::downgrade);
+    }
+
+    /// Adds `delta` to the count of the last node of `path` (root first) and
+    /// to the totals of every node on it.
+    pub(crate) fn add_count(path: &[RcRefcellRBTNode<K>], delta: isize) {
+        if let Some(last) = path.last() {
+            let mut last = last.borrow_mut();
+            last.count = last.count.checked_add_signed(delta).unwrap();
+        }
+        for node in path {
+            let mut node = node.borrow_mut();
+            node.total = node.total.checked_add_signed(delta).unwrap();
+        }
+    }
+}
+
+impl<K: Ord + Clone + Debug> Node<K> for RBNode<K> {
+    fn new(key: K) -> OptionNode<K> {
         Some(Rc::new(RefCell::new(RBNode {
             color: NodeColor::Red,
             key,
             parent: None,
             left: None,
             right: None,
+            size: 1,
+            count: 1,
+            total: 1,
+            height: 1,
+            black_height: 1,
+            leaves: 1,
         })))
     }
 
-    fn get_left(&self) -> &OptionNode {
+    fn get_left(&self) -> &OptionNode<K> {
         &self.left
     }
 
-    fn get_right(&self) -> &OptionNode {
+    fn get_right(&self) -> &OptionNode<K> {
         &self.right
     }
 
-    fn get_key(&self) -> i64 {
-        self.key
+    fn get_key(&self) -> &K {
+        &self.key
+    }
+
+    fn get_size(&self) -> usize {
+        self.size
+    }
+
+    fn get_count(&self) -> usize {
+        self.count
+    }
+
+    fn get_total(&self) -> usize {
+        self.total
+    }
+
+    fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    fn count_leaves(&self) -> u32 {
+        self.leaves
+    }
+
+    fn count_nodes(&self) -> u32 {
+        self.size as u32
     }
 
-    fn print_node(&self, prefix_space: &String, child_prefix: String, is_right: bool) {
+    fn render_node(&self, out: &mut dyn fmt::Write, prefix_space: &str, child_prefix: String, is_right: bool) -> fmt::Result {
         if child_prefix == "Root" {
-            print!("\n")
+            writeln!(out)?;
         }
         let color = if self.color == NodeColor::Black {"Black"} else {"Red"};
         
@@ -64,9 +130,9 @@ impl Node for RBNode {
                 }
             
             left.borrow()
-                .print_node(&new_prefix_space_left, "L".to_string(), false);
+                .render_node(out, &new_prefix_space_left, "L".to_string(), false)?;
         }
-        println!("{}{} {:?} {}", prefix_space, child_prefix, self.key, color);
+        writeln!(out, "{}{} {:?} {}", prefix_space, child_prefix, self.key, color)?;
         if let Some(right) = self.get_right() {
             match is_right{
                     true => {
@@ -83,7 +149,8 @@ impl Node for RBNode {
             
             right
                 .borrow()
-                .print_node(&new_prefix_space_right, "R".to_string(), true);
+                .render_node(out, &new_prefix_space_right, "R".to_string(), true)?;
         }
+        Ok(())
     }
 }
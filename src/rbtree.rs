@@ -1,74 +1,467 @@
-use std::cell::{RefCell, RefMut};
+use std::cell::RefMut;
+use std::cmp::{max, Ordering};
+use std::fmt::{self, Debug, Display};
 use std::rc::Rc;
 
+use crate::dot::{color_style, DotNodeStyle};
+use crate::error::TreeError;
+use crate::invariant::{validate_shape, InvariantViolation, Rule};
+use crate::iter::Iter;
 use crate::node::Node;
 use crate::rbnode::{NodeColor, OptionNode, RBNode, RcRefcellRBTNode};
-use crate::tree::Tree;
+use crate::render::{color_label, RenderStyle};
+use crate::setops::impl_set_operators;
+use crate::tree::{collect_sorted, merge_sorted, Tree};
 
-pub struct RBTree {
-    _root: OptionNode,
+pub struct RBTree<K> {
+    _root: OptionNode<K>,
 }
 
-impl Tree<RBNode> for RBTree {
+impl<K: Ord + Clone + Debug> Tree<K> for RBTree<K> {
+    type NodeRef = RcRefcellRBTNode<K>;
+
     fn new() -> Self {
         RBTree { _root: None }
     }
 
-    fn get_root(&self) -> &OptionNode {
-        &self._root
+    fn get_root(&self) -> Option<RcRefcellRBTNode<K>> {
+        self._root.clone()
+    }
+
+    fn get_left(&self, node: &RcRefcellRBTNode<K>) -> Option<RcRefcellRBTNode<K>> {
+        node.borrow().get_left().clone()
+    }
+
+    fn get_right(&self, node: &RcRefcellRBTNode<K>) -> Option<RcRefcellRBTNode<K>> {
+        node.borrow().get_right().clone()
+    }
+
+    fn with_key<R>(&self, node: &RcRefcellRBTNode<K>, f: impl FnOnce(&K) -> R) -> R {
+        f(node.borrow().get_key())
+    }
+
+    fn get_node_size(&self, node: &RcRefcellRBTNode<K>) -> usize {
+        node.borrow().get_size()
+    }
+
+    fn get_node_count(&self, node: &RcRefcellRBTNode<K>) -> usize {
+        node.borrow().get_count()
+    }
+
+    fn get_node_total(&self, node: &RcRefcellRBTNode<K>) -> usize {
+        node.borrow().get_total()
+    }
+
+    fn get_node_height(&self, node: &RcRefcellRBTNode<K>) -> u32 {
+        node.borrow().get_height()
+    }
+
+    fn count_node_leaves(&self, node: &RcRefcellRBTNode<K>) -> u32 {
+        node.borrow().count_leaves()
     }
 
-    fn insert(&mut self, key: i64) {
+    fn insert(&mut self, key: K) -> bool {
         if self.is_empty() {
-            let new_node = RBNode::new(key);
-            new_node.as_ref().unwrap().borrow_mut().color = NodeColor::Black;
-            self._root = new_node;
-            return;
+            self._attach(None, key);
+            return true;
         }
-        let (exists, parent_option) = self.search(key);
+        let (exists, parent_option) = self.search(&key);
 
         if exists {
-            return;
+            return false;
         }
 
-        let new_child_node = RBNode::new(key).as_ref().unwrap().clone();
-        let new_child_ref_clone = new_child_node.clone();
-        let new_child = Some(new_child_node);
+        self._attach(parent_option, key);
+        true
+    }
 
-        let parent_ref = Rc::clone(&parent_option.as_ref().unwrap());
-        new_child.as_ref().unwrap().borrow_mut().parent = Some(parent_ref);
+    /// Panics if the tree turns out to be corrupted; see `try_delete`.
+    fn delete<Q>(&mut self, key: &Q) -> Option<K>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match self.try_delete(key) {
+            Ok(removed) => removed,
+            Err(error) => panic!("RBTree::delete: {}", error),
+        }
+    }
 
-        let should_be_left_child = key < parent_option.as_ref().unwrap().borrow().key;
-        if should_be_left_child {
-            parent_option.as_ref().unwrap().borrow_mut().left = new_child;
+    fn insert_at(&mut self, mut path: Vec<RcRefcellRBTNode<K>>, key: K) {
+        self._attach(path.pop(), key);
+    }
+
+    /// Panics if the tree turns out to be corrupted, like `delete`.
+    fn delete_at(&mut self, path: Vec<RcRefcellRBTNode<K>>) -> K {
+        match self.delete_node(path.last().unwrap()) {
+            Ok((removed, _)) => removed,
+            Err(error) => panic!("RBTree::delete: {}", error),
+        }
+    }
+
+    fn render_sideways(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        match &self._root {
+            Some(root) => root.borrow().render_node(out, "", "Root".to_string(), false),
+            None => Ok(()),
+        }
+    }
+
+    fn dot_node_style(&self, node: &RcRefcellRBTNode<K>) -> DotNodeStyle {
+        color_style(&node.borrow().color)
+    }
+
+    fn render_label(&self, node: &RcRefcellRBTNode<K>) -> String {
+        let node = node.borrow();
+        color_label(&node.key, &node.color)
+    }
+}
+
+impl<K: Ord + Clone + Debug> RBTree<K> {
+    /// Like `delete`, but reports a broken red-black invariant found while
+    /// rebalancing instead of panicking. The tree may be left half repaired.
+    pub fn try_delete<Q>(&mut self, key: &Q) -> Result<Option<K>, TreeError>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (true, Some(node)) = self.search(key) else {
+            return Ok(None);
+        };
+        self._delete_private(&node).map(|(removed, _)| Some(removed))
+    }
+
+    /// Removes the key held by `node` without searching for it. Returns the
+    /// key and the node that now holds the next one, which need not be the
+    /// node that held it before.
+    pub(crate) fn delete_node(&mut self, node: &RcRefcellRBTNode<K>) -> Result<(K, OptionNode<K>), TreeError> {
+        self._delete_private(node)
+    }
+
+    /// Inserts `key` as a child of `parent`, or as the root of an empty tree.
+    /// The caller makes sure that `key` belongs in the free slot on its side.
+    pub(crate) fn insert_below(&mut self, parent: OptionNode<K>, key: K) {
+        self._attach(parent, key);
+    }
+
+    /// Builds a balanced tree from keys in ascending order in O(n), with the
+    /// bottom level red. Repeated keys are kept once.
+    ///
+    /// Panics if a key is smaller than the one before it.
+    pub fn from_sorted_iter(keys: impl IntoIterator<Item = K>) -> Self {
+        Self::from_sorted_vec(collect_sorted(keys, "RBTree::from_sorted_iter"))
+    }
+
+    pub(crate) fn from_root(root: OptionNode<K>) -> Self {
+        RBTree { _root: root }
+    }
+
+    /// Builds a balanced tree from strictly ascending keys in O(n). Only the
+    /// bottom level is red, so every path has the same black height.
+    pub(crate) fn from_sorted_vec(keys: Vec<K>) -> Self {
+        let len = keys.len();
+        let red_depth = len.checked_ilog2().unwrap_or(0);
+        Self::from_root(Self::_build_sorted(&mut keys.into_iter(), len, 0, red_depth))
+    }
+
+    fn _build_sorted(keys: &mut impl Iterator<Item = K>, len: usize, depth: u32, red_depth: u32) -> OptionNode<K> {
+        if len == 0 {
+            return None;
+        }
+        let left = Self::_build_sorted(keys, len / 2, depth + 1, red_depth);
+        let key = keys.next().unwrap();
+        let right = Self::_build_sorted(keys, len - len / 2 - 1, depth + 1, red_depth);
+        let color = if depth == red_depth && depth > 0 { NodeColor::Red } else { NodeColor::Black };
+        Some(Self::join_node(key, color, left, right))
+    }
+
+    /// Makes a node above two finished subtrees, pointing their `parent`
+    /// links at it and computing its cached fields. Does not recolor.
+    pub(crate) fn join_node(key: K, color: NodeColor, left: OptionNode<K>, right: OptionNode<K>) -> RcRefcellRBTNode<K> {
+        let node = RBNode::new(key).unwrap();
+        node.borrow_mut().color = color;
+        Self::_link(&node, left, right);
+        node
+    }
+
+    /// Builds the tree holding the keys of `left`, `pivot` and those of
+    /// `right`, in O(log n).
+    ///
+    /// Panics unless every key of `left` is less than `pivot` and every key
+    /// of `right` greater.
+    pub fn join(mut left: Self, pivot: K, mut right: Self) -> Self {
+        assert!(
+            left.get_max().is_none_or(|max| max < pivot) && right.get_min().is_none_or(|min| pivot < min),
+            "RBTree::join: keys are not in order"
+        );
+        let pivot = RBNode::new(pivot).unwrap();
+        Self::_from_subtree(Some(Self::_join(left._root.take(), pivot, right._root.take())))
+    }
+
+    /// Moves the keys greater than or equal to `key` into a new tree, in
+    /// O(log n).
+    pub fn split_off<Q>(&mut self, key: &Q) -> Self
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (less, rest) = Self::_split(self._root.take(), key);
+        *self = Self::_from_subtree(less);
+        Self::_from_subtree(rest)
+    }
+
+    /// Moves every key of `other` into this tree, leaving `other` empty. Takes
+    /// O(log n) when all keys of one tree are smaller than those of the
+    /// other and O(n + m) otherwise.
+    pub fn append(&mut self, other: &mut Self) {
+        let (mut left, mut right) = (Self::from_root(self._root.take()), Self::from_root(other._root.take()));
+        if matches!((right.get_max(), left.get_min()), (Some(max), Some(min)) if max < min) {
+            std::mem::swap(&mut left, &mut right);
+        }
+        *self = match right.get_min() {
+            None => left,
+            Some(min) if left.get_max().is_none_or(|max| max < min) => {
+                let pivot = right.delete(&min).unwrap();
+                Self::join(left, pivot, right)
+            }
+            Some(_) => Self::from_sorted_vec(merge_sorted(left.in_order_traversal(), right.in_order_traversal())),
+        };
+    }
+
+    /// Wraps a subtree cut out of another tree, detaching it and painting its
+    /// root black.
+    fn _from_subtree(root: OptionNode<K>) -> Self {
+        if let Some(root) = &root {
+            let mut root_node = root.borrow_mut();
+            root_node.parent = None;
+            root_node.color = NodeColor::Black;
+        }
+        Self::from_root(root)
+    }
+
+    /// Joins two red-black subtrees whose roots may be red, using `pivot` as
+    /// the node between them. Walks down the spine of the tree with the larger
+    /// black height until both sides match, like an insert.
+    fn _join(left: OptionNode<K>, pivot: RcRefcellRBTNode<K>, right: OptionNode<K>) -> RcRefcellRBTNode<K> {
+        let (left_black_height, right_black_height) = (Self::_full_black_height(&left), Self::_full_black_height(&right));
+        if left_black_height > right_black_height {
+            let root = Self::_join_right(left, pivot, right, right_black_height);
+            if Self::_return_color(&root) == NodeColor::Red && Self::_is_red(&root.borrow().right) {
+                root.borrow_mut().color = NodeColor::Black;
+            }
+            root
+        } else if right_black_height > left_black_height {
+            let root = Self::_join_left(left, pivot, right, left_black_height);
+            if Self::_return_color(&root) == NodeColor::Red && Self::_is_red(&root.borrow().left) {
+                root.borrow_mut().color = NodeColor::Black;
+            }
+            root
         } else {
-            parent_option.as_ref().unwrap().borrow_mut().right = new_child;
+            let color = if Self::_is_red(&left) || Self::_is_red(&right) { NodeColor::Black } else { NodeColor::Red };
+            pivot.borrow_mut().color = color;
+            Self::_link(&pivot, left, right);
+            pivot
+        }
+    }
+
+    /// Hangs `pivot` and `right` off the right spine of `left`, at the first
+    /// black node whose black height matches `right_black_height`.
+    fn _join_right(left: OptionNode<K>, pivot: RcRefcellRBTNode<K>, right: OptionNode<K>, right_black_height: u32) -> RcRefcellRBTNode<K> {
+        if !Self::_is_red(&left) && Self::_full_black_height(&left) == right_black_height {
+            pivot.borrow_mut().color = NodeColor::Red;
+            Self::_link(&pivot, left, right);
+            return pivot;
         }
+        let left = left.unwrap();
+        let (inner_left, inner_right) = {
+            let mut left_node = left.borrow_mut();
+            (left_node.left.take(), left_node.right.take())
+        };
+        let joined = Self::_join_right(inner_right, pivot, right, right_black_height);
+        Self::_link(&left, inner_left, Some(joined.clone()));
 
-        self._insert_repair(new_child_ref_clone);
+        let joined_right = joined.borrow().right.clone();
+        if Self::_return_color(&left) == NodeColor::Black && Self::_return_color(&joined) == NodeColor::Red && Self::_is_red(&joined_right) {
+            joined_right.unwrap().borrow_mut().color = NodeColor::Black;
+            return Self::_rotate_subtree_left(left);
+        }
+        left
     }
 
-    fn delete(&mut self, key: i64) {
-        let (exists, result) = self.search(key);
-        if exists {
-            let mut result_node_ref: &RcRefcellRBTNode = result.as_ref().unwrap();
-            let _ = self._delete_private(&mut result_node_ref);
+    fn _join_left(left: OptionNode<K>, pivot: RcRefcellRBTNode<K>, right: OptionNode<K>, left_black_height: u32) -> RcRefcellRBTNode<K> {
+        if !Self::_is_red(&right) && Self::_full_black_height(&right) == left_black_height {
+            pivot.borrow_mut().color = NodeColor::Red;
+            Self::_link(&pivot, left, right);
+            return pivot;
+        }
+        let right = right.unwrap();
+        let (inner_left, inner_right) = {
+            let mut right_node = right.borrow_mut();
+            (right_node.left.take(), right_node.right.take())
+        };
+        let joined = Self::_join_left(left, pivot, inner_left, left_black_height);
+        Self::_link(&right, Some(joined.clone()), inner_right);
+
+        let joined_left = joined.borrow().left.clone();
+        if Self::_return_color(&right) == NodeColor::Black && Self::_return_color(&joined) == NodeColor::Red && Self::_is_red(&joined_left) {
+            joined_left.unwrap().borrow_mut().color = NodeColor::Black;
+            return Self::_rotate_subtree_right(right);
+        }
+        right
+    }
+
+    /// Splits the subtree at `node` into the keys less than `key` and the
+    /// rest, in O(log n). Either part may come back with a red root.
+    fn _split<Q>(node: OptionNode<K>, key: &Q) -> (OptionNode<K>, OptionNode<K>)
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let Some(node) = node else {
+            return (None, None);
+        };
+        let (left, right) = {
+            let mut this_node = node.borrow_mut();
+            (this_node.left.take(), this_node.right.take())
+        };
+        let ordering = node.borrow().key.borrow().cmp(key);
+        match ordering {
+            Ordering::Greater => {
+                let (less, rest) = Self::_split(left, key);
+                (less, Some(Self::_join(rest, node, right)))
+            }
+            Ordering::Less => {
+                let (less, rest) = Self::_split(right, key);
+                (Some(Self::_join(left, node, less)), rest)
+            }
+            Ordering::Equal => (left, Some(Self::_join(None, node, right))),
+        }
+    }
+
+    /// Points `node` at `left` and `right` and them back at it, then
+    /// recomputes its cached fields.
+    fn _link(node: &RcRefcellRBTNode<K>, left: OptionNode<K>, right: OptionNode<K>) {
+        for child in left.iter().chain(&right) {
+            child.borrow_mut().set_parent(&Some(node.clone()));
+        }
+        {
+            let mut this_node = node.borrow_mut();
+            this_node.left = left;
+            this_node.right = right;
         }
+        Self::_update(node);
     }
 
-    fn print_tree(&self) {
-        if let Some(root) = &self.get_root() {
-            root.borrow()
-                .print_node(&"".to_string(), "Root".to_string(), false);
+    /// Rotates a subtree that is not attached to the tree yet and returns
+    /// its new root.
+    fn _rotate_subtree_left(node: RcRefcellRBTNode<K>) -> RcRefcellRBTNode<K> {
+        let new_root = node.borrow_mut().right.take().unwrap();
+        let (left, inner) = (node.borrow_mut().left.take(), new_root.borrow_mut().left.take());
+        Self::_link(&node, left, inner);
+        let right = new_root.borrow_mut().right.take();
+        Self::_link(&new_root, Some(node), right);
+        new_root
+    }
+
+    fn _rotate_subtree_right(node: RcRefcellRBTNode<K>) -> RcRefcellRBTNode<K> {
+        let new_root = node.borrow_mut().left.take().unwrap();
+        let (inner, right) = (new_root.borrow_mut().right.take(), node.borrow_mut().right.take());
+        Self::_link(&node, inner, right);
+        let left = new_root.borrow_mut().left.take();
+        Self::_link(&new_root, left, Some(node));
+        new_root
+    }
+
+    /// Black nodes on a path from `node` down to a NIL leaf, counting both.
+    fn _full_black_height(node: &OptionNode<K>) -> u32 {
+        node.as_ref().map_or(1, |node| {
+            let node = node.borrow();
+            node.black_height + u32::from(node.color == NodeColor::Black)
+        })
+    }
+
+    fn _is_red(node: &OptionNode<K>) -> bool {
+        node.as_ref().is_some_and(|node| node.borrow().color == NodeColor::Red)
+    }
+
+    /// Number of black nodes on every path from the root to a leaf.
+    pub fn black_height(&self) -> u32 {
+        self._root.as_ref().map_or(0, |root| root.borrow().black_height)
+    }
+
+    /// Checks the red-black rules, the `parent` links and every cached field.
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>> {
+        validate_shape(self)?;
+        if let Some(root) = &self._root {
+            if root.borrow().color == NodeColor::Red {
+                return Err(InvariantViolation::new(root.borrow().key.clone(), Rule::RedRoot));
+            }
+            Self::_validate_node(root, &None)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the black height of `node`, counted like `RBNode::black_height`.
+    fn _validate_node(node: &RcRefcellRBTNode<K>, parent: &OptionNode<K>) -> Result<u32, InvariantViolation<K>> {
+        let this_node = node.borrow();
+        let violation = |rule| Err(InvariantViolation::new(this_node.key.clone(), rule));
+
+        let parent_matches = match (this_node.get_parent(), parent) {
+            (None, None) => true,
+            (Some(linked), Some(parent)) => Rc::ptr_eq(&linked, parent),
+            _ => false,
+        };
+        if !parent_matches {
+            return violation(Rule::ParentLink);
+        }
+        if this_node.color == NodeColor::Red
+            && parent.as_ref().is_some_and(|parent| parent.borrow().color == NodeColor::Red)
+        {
+            return violation(Rule::RedRedEdge);
+        }
+
+        let mut black_heights = [1, 1];
+        for (child, black_height) in [&this_node.left, &this_node.right].into_iter().zip(&mut black_heights) {
+            if let Some(child) = child {
+                let is_black = child.borrow().color == NodeColor::Black;
+                *black_height = Self::_validate_node(child, &Some(node.clone()))? + is_black as u32;
+            }
+        }
+        if black_heights[0] != black_heights[1] {
+            return violation(Rule::BlackHeight);
+        }
+        if this_node.black_height != black_heights[0] {
+            return violation(Rule::StoredBlackHeight);
+        }
+        Ok(black_heights[0])
+    }
+
+    fn _attach(&mut self, parent_option: OptionNode<K>, key: K) {
+        if parent_option.is_none() {
+            let new_node = RBNode::new(key);
+            new_node.as_ref().unwrap().borrow_mut().color = NodeColor::Black;
+            self._root = new_node;
+            return;
+        }
+
+        let should_be_left_child = key < parent_option.as_ref().unwrap().borrow().key;
+        let new_child_node = RBNode::new(key).unwrap();
+        let new_child_ref_clone = new_child_node.clone();
+        let new_child = Some(new_child_node);
+
+        new_child.as_ref().unwrap().borrow_mut().set_parent(&parent_option);
+
+        if should_be_left_child {
+            parent_option.as_ref().unwrap().borrow_mut().left = new_child;
         } else {
-            println!("This tree is empty!");
+            parent_option.as_ref().unwrap().borrow_mut().right = new_child;
         }
+
+        self._insert_repair(new_child_ref_clone.clone());
+        Self::_update_upward(Some(new_child_ref_clone));
     }
-}
 
-impl RBTree {
-    fn _insert_repair(&mut self, new_child: RcRefcellRBTNode) {
-        let mut child: RcRefcellRBTNode = new_child;
+    fn _insert_repair(&mut self, new_child: RcRefcellRBTNode<K>) {
+        let mut child: RcRefcellRBTNode<K> = new_child;
         loop {
             if child.borrow().parent.is_none() {
                 Self::_change_color(&mut self._root.as_ref().unwrap(), NodeColor::Black);
@@ -77,9 +470,9 @@ impl RBTree {
             if !Self::_is_parent_red(&child) {
                 return;
             }
-            let mut parent = child.borrow().parent.clone().unwrap();
-            let grandparent = parent.borrow().parent.clone().unwrap();
-            let uncle: Rc<RefCell<RBNode>>;
+            let mut parent = child.borrow().get_parent().unwrap();
+            let grandparent = parent.borrow().get_parent().unwrap();
+            let uncle: RcRefcellRBTNode<K>;
 
             let parent_left_side: bool = RBTree::_is_left_child(&parent);
             if parent_left_side {
@@ -123,42 +516,41 @@ impl RBTree {
         }
     }
 
-    fn _is_left_child(node: &RcRefcellRBTNode) -> bool {
-        node.borrow().parent.as_ref().map_or(false, |parent| {
+    fn _is_left_child(node: &RcRefcellRBTNode<K>) -> bool {
+        node.borrow().get_parent().is_some_and(|parent| {
             parent
                 .borrow()
                 .left
                 .as_ref()
-                .map_or(false, |left| left.borrow().key == node.borrow().key)
+                .is_some_and(|left| left.borrow().key == node.borrow().key)
         })
     }
 
-    fn _is_parent_red(node: &RcRefcellRBTNode) -> bool {
+    fn _is_parent_red(node: &RcRefcellRBTNode<K>) -> bool {
         node.borrow()
-            .parent
-            .as_ref()
-            .map_or(false, |parent| parent.borrow().color == NodeColor::Red)
+            .get_parent()
+            .is_some_and(|parent| parent.borrow().color == NodeColor::Red)
     }
 
-    fn _left_rotate(&mut self, rotation_node: &RcRefcellRBTNode) {
+    fn _left_rotate(&mut self, rotation_node: &RcRefcellRBTNode<K>) {
         {
-            let parent: &OptionNode = &rotation_node.borrow().parent;
-            let right: &OptionNode = &rotation_node.borrow().right;
+            let parent: OptionNode<K> = rotation_node.borrow().get_parent();
+            let right: &OptionNode<K> = &rotation_node.borrow().right;
             if rotation_node.borrow().parent.is_none() {
                 self._root = right.clone();
             }
-            if let Some(node) = parent {
+            if let Some(node) = &parent {
                 if Self::_is_left_child(rotation_node) {
                     node.borrow_mut().left = right.clone();
                 } else {
                     node.borrow_mut().right = right.clone();
                 }
             }
-            right.as_ref().unwrap().borrow_mut().parent = parent.clone();
+            right.as_ref().unwrap().borrow_mut().set_parent(&parent);
         }
-        let right_node: Rc<RefCell<RBNode>> =
+        let right_node: RcRefcellRBTNode<K> =
             rotation_node.borrow().right.as_ref().unwrap().clone();
-        rotation_node.borrow_mut().parent = Some(Rc::clone(&right_node));
+        rotation_node.borrow_mut().parent = Some(Rc::downgrade(&right_node));
 
         if right_node.borrow().left.is_some() {
             rotation_node.borrow_mut().right =
@@ -169,31 +561,33 @@ impl RBTree {
                 .as_ref()
                 .unwrap()
                 .borrow_mut()
-                .parent = Some(Rc::clone(rotation_node));
+                .parent = Some(Rc::downgrade(rotation_node));
         } else {
             rotation_node.borrow_mut().right = None;
         }
         right_node.borrow_mut().left = Some(rotation_node.clone());
+        Self::_update(rotation_node);
+        Self::_update(&right_node);
     }
 
-    fn _right_rotate(&mut self, rotation_node: &RcRefcellRBTNode) {
+    fn _right_rotate(&mut self, rotation_node: &RcRefcellRBTNode<K>) {
         {
-            let parent: &OptionNode = &rotation_node.borrow().parent;
-            let left: &OptionNode = &rotation_node.borrow().left;
+            let parent: OptionNode<K> = rotation_node.borrow().get_parent();
+            let left: &OptionNode<K> = &rotation_node.borrow().left;
             if rotation_node.borrow().parent.is_none() {
                 self._root = left.clone();
             }
-            if let Some(node) = parent {
+            if let Some(node) = &parent {
                 if Self::_is_left_child(rotation_node) {
                     node.borrow_mut().left = left.clone();
                 } else {
                     node.borrow_mut().right = left.clone();
                 }
             }
-            left.as_ref().unwrap().borrow_mut().parent = parent.clone();
+            left.as_ref().unwrap().borrow_mut().set_parent(&parent);
         }
-        let left_node: Rc<RefCell<RBNode>> = rotation_node.borrow().left.as_ref().unwrap().clone();
-        rotation_node.borrow_mut().parent = Some(Rc::clone(&left_node));
+        let left_node: RcRefcellRBTNode<K> = rotation_node.borrow().left.as_ref().unwrap().clone();
+        rotation_node.borrow_mut().parent = Some(Rc::downgrade(&left_node));
         if left_node.borrow().right.is_some() {
             rotation_node.borrow_mut().left =
                 Some(left_node.borrow().right.as_ref().unwrap().clone());
@@ -203,14 +597,16 @@ impl RBTree {
                 .as_ref()
                 .unwrap()
                 .borrow_mut()
-                .parent = Some(Rc::clone(rotation_node));
+                .parent = Some(Rc::downgrade(rotation_node));
         } else {
             rotation_node.borrow_mut().left = None;
         }
         left_node.borrow_mut().right = Some(rotation_node.clone());
+        Self::_update(rotation_node);
+        Self::_update(&left_node);
     }
 
-    fn _recur_right_child(node: OptionNode) -> OptionNode {
+    fn _recur_right_child(node: OptionNode<K>) -> OptionNode<K> {
         if let Some(inner_node) = &node {
             if inner_node.borrow().right.is_some() {
                 return Self::_recur_right_child(inner_node.borrow().right.clone());
@@ -219,163 +615,223 @@ impl RBTree {
         node
     }
 
-    fn _find_replacement_node(node: &RcRefcellRBTNode) -> OptionNode {
-        return if node.borrow().left.is_some() {
+    fn _find_replacement_node(node: &RcRefcellRBTNode<K>) -> OptionNode<K> {
+        if node.borrow().left.is_some() {
             Self::_recur_right_child(node.borrow().left.clone())
         } else if node.borrow().right.is_some() {
             node.borrow().right.clone()
         } else {
             None
-        };
+        }
     }
 
-    fn _delete_private(&mut self, node: &mut &RcRefcellRBTNode) -> Result<(), String> {
+    /// Node holding the next key, found through the parent links.
+    fn _next_node(node: &RcRefcellRBTNode<K>) -> OptionNode<K> {
+        let right = node.borrow().right.clone();
+        if let Some(mut next) = right {
+            loop {
+                let left = next.borrow().left.clone();
+                match left {
+                    Some(left) => next = left,
+                    None => return Some(next),
+                }
+            }
+        }
+        let mut current = node.clone();
+        loop {
+            let parent = current.borrow().get_parent()?;
+            if parent.borrow().left.as_ref().is_some_and(|left| Rc::ptr_eq(left, &current)) {
+                return Some(parent);
+            }
+            current = parent;
+        }
+    }
+
+    /// Returns the removed key and the node that holds the next key once the
+    /// deletion is done.
+    fn _delete_private(&mut self, node: &RcRefcellRBTNode<K>) -> Result<(K, OptionNode<K>), TreeError> {
+        // Only the root's one-child case below moves the next key to another
+        // node; everywhere else the next key's node stays linked.
+        let next = Self::_next_node(node);
         let replacement = Self::_find_replacement_node(node);
-        let parent = node.borrow().parent.clone();
+        let parent = node.borrow().get_parent();
         let double_black = Self::_return_color(node) == NodeColor::Black
-            && (replacement.is_none()
-                || Self::_return_color(replacement.as_ref().unwrap()) == NodeColor::Black);
+            && replacement
+                .as_ref()
+                .is_none_or(|replacement| Self::_return_color(replacement) == NodeColor::Black);
 
-        if replacement.is_none() {
-            if node.borrow().parent.is_none() {
+        let Some(replacement) = replacement else {
+            let Some(parent) = parent else {
                 self._root = None;
-                return Ok(());
-            }
+                return Ok((node.borrow().key.clone(), None));
+            };
             if double_black {
-                self._delete_repair(node);
+                self._delete_repair(node)?;
             }
             if Self::_is_left_child(node) {
-                parent.as_ref().unwrap().borrow_mut().left = None;
+                parent.borrow_mut().left = None;
             } else {
-                parent.as_ref().unwrap().borrow_mut().right = None;
+                parent.borrow_mut().right = None;
             }
+            Self::_update_upward(Some(parent));
 
-            return Ok(());
-        }
+            return Ok((node.borrow().key.clone(), next));
+        };
         if node.borrow().left.is_none() || node.borrow().right.is_none() {
-            if node.borrow().parent.is_none() {
-                let temp = replacement.as_ref().unwrap().borrow().key;
-                let mut root: RefMut<RBNode> = self._root.as_ref().unwrap().borrow_mut();
-                root.key = temp;
+            let Some(parent_node) = &parent else {
+                // The root keeps its place and takes over its only child's key,
+                // which is the next key if that child is on the right.
+                let next = if node.borrow().right.is_some() { Some(node.clone()) } else { next };
+                let temp = replacement.borrow().key.clone();
+                let count = replacement.borrow().count;
+                let mut root: RefMut<RBNode<K>> = node.borrow_mut();
+                root.count = count;
+                let removed = std::mem::replace(&mut root.key, temp);
                 root.left = None;
                 root.right = None;
-                return Ok(());
-            }
+                drop(root);
+                Self::_update(node);
+                return Ok((removed, next));
+            };
             if !Self::_is_left_child(node) {
-                parent.as_ref().unwrap().borrow_mut().right = replacement.clone();
+                parent_node.borrow_mut().right = Some(replacement.clone());
             } else {
-                parent.as_ref().unwrap().borrow_mut().left = replacement.clone();
+                parent_node.borrow_mut().left = Some(replacement.clone());
             }
-            replacement.as_ref().unwrap().borrow_mut().parent = parent.clone();
+            replacement.borrow_mut().set_parent(&parent);
             if !double_black {
-                Self::_change_color(&mut replacement.as_ref().unwrap(), NodeColor::Black);
+                Self::_change_color(&mut &replacement, NodeColor::Black);
             } else {
-                self._delete_repair(&replacement.unwrap());
+                self._delete_repair(&replacement)?;
             }
+            Self::_update_upward(Some(replacement));
 
-            return Ok(());
+            return Ok((node.borrow().key.clone(), next));
         }
-        node.borrow_mut().key = replacement.as_ref().unwrap().borrow().key;
-        self._delete_private(&mut replacement.as_ref().unwrap())
-            .unwrap();
+        let replacement_key = replacement.borrow().key.clone();
+        node.borrow_mut().count = replacement.borrow().count;
+        let removed = std::mem::replace(&mut node.borrow_mut().key, replacement_key);
+        // `node` takes its predecessor's key, so the next key stays put.
+        self._delete_private(&replacement)?;
 
-        Ok(())
+        Ok((removed, next))
     }
 
-    fn _delete_repair(&mut self, node: &RcRefcellRBTNode) {
-        if node.borrow().parent.is_none() {
-            return;
-        }
-        let parent = Some(Rc::clone(node.borrow().parent.as_ref().unwrap()));
-        let sibling = Self::_return_node_same_level(node);
+    fn _delete_repair(&mut self, node: &RcRefcellRBTNode<K>) -> Result<(), TreeError> {
+        let Some(parent) = node.borrow().get_parent() else {
+            return Ok(());
+        };
+        // A black non-root node always has a sibling in a valid tree.
+        let sibling = Self::_return_node_same_level(node).ok_or(TreeError::MissingSibling)?;
 
-        if sibling.is_none() {
-            self._delete_repair(&parent.unwrap());
-            return;
-        }
-        if Self::_return_color(sibling.as_ref().unwrap()) == NodeColor::Black {
-            if !Self::_has_red_child(sibling.as_ref().unwrap()) {
-                Self::_change_color(&mut sibling.as_ref().unwrap(), NodeColor::Red);
-                if Self::_return_color(parent.as_ref().unwrap()) == NodeColor::Red {
-                    Self::_change_color(&mut parent.as_ref().unwrap(), NodeColor::Black);
-                    return;
+        if Self::_return_color(&sibling) == NodeColor::Black {
+            if !Self::_has_red_child(&sibling) {
+                Self::_change_color(&mut &sibling, NodeColor::Red);
+                if Self::_return_color(&parent) == NodeColor::Red {
+                    Self::_change_color(&mut &parent, NodeColor::Black);
+                    return Ok(());
                 }
-                self._delete_repair(&parent.unwrap());
-
-                return;
+                return self._delete_repair(&parent);
             }
+            let sibling_left = sibling.borrow().left.clone();
+            let sibling_right = sibling.borrow().right.clone();
+            let left_is_red = sibling_left
+                .as_ref()
+                .is_some_and(|left| Self::_return_color(left) == NodeColor::Red);
+
             if !Self::_is_left_child(node) {
-                if sibling.as_ref().unwrap().borrow().left.is_some()
-                    && Self::_return_color(
-                        sibling.as_ref().unwrap().borrow().left.as_ref().unwrap(),
-                    ) == NodeColor::Red
-                {
-                    Self::_change_color(
-                        &mut sibling.as_ref().unwrap().borrow().left.as_ref().unwrap(),
-                        NodeColor::Black,
-                    );
-                    let parent_color = Self::_return_color(parent.as_ref().unwrap());
-                    Self::_change_color(&mut sibling.as_ref().unwrap(), parent_color);
-                    self._right_rotate(parent.as_ref().unwrap());
-                    Self::_change_color(&mut parent.as_ref().unwrap(), NodeColor::Black);
-                    return;
+                if left_is_red {
+                    Self::_change_color(&mut sibling_left.as_ref().ok_or(TreeError::MissingChild)?, NodeColor::Black);
+                    let parent_color = Self::_return_color(&parent);
+                    Self::_change_color(&mut &sibling, parent_color);
+                    self._right_rotate(&parent);
+                    Self::_change_color(&mut &parent, NodeColor::Black);
+                    return Ok(());
                 }
-                let parent_color = Self::_return_color(parent.as_ref().unwrap());
-                Self::_change_color(
-                    &mut sibling.as_ref().unwrap().borrow().right.as_ref().unwrap(),
-                    parent_color,
-                );
-                self._left_rotate(sibling.as_ref().unwrap());
-                self._right_rotate(parent.as_ref().unwrap());
-                Self::_change_color(&mut parent.as_ref().unwrap(), NodeColor::Black);
+                let parent_color = Self::_return_color(&parent);
+                Self::_change_color(&mut sibling_right.as_ref().ok_or(TreeError::MissingChild)?, parent_color);
+                self._left_rotate(&sibling);
+                self._right_rotate(&parent);
+                Self::_change_color(&mut &parent, NodeColor::Black);
 
-                return;
+                return Ok(());
             }
-            if sibling.as_ref().unwrap().borrow().left.is_some()
-                && Self::_return_color(sibling.as_ref().unwrap().borrow().left.as_ref().unwrap())
-                    == NodeColor::Red
-            {
-                let parent_color: NodeColor = Self::_return_color(parent.as_ref().unwrap());
-                Self::_change_color(
-                    &mut sibling.as_ref().unwrap().borrow().left.as_ref().unwrap(),
-                    parent_color,
-                );
-                self._right_rotate(sibling.as_ref().unwrap());
-                self._left_rotate(parent.as_ref().unwrap());
-                Self::_change_color(&mut parent.as_ref().unwrap(), NodeColor::Black);
-                return;
+            if left_is_red {
+                let parent_color: NodeColor = Self::_return_color(&parent);
+                Self::_change_color(&mut sibling_left.as_ref().ok_or(TreeError::MissingChild)?, parent_color);
+                self._right_rotate(&sibling);
+                self._left_rotate(&parent);
+                Self::_change_color(&mut &parent, NodeColor::Black);
+                return Ok(());
             }
-            Self::_change_color(
-                &mut sibling.as_ref().unwrap().borrow().right.as_ref().unwrap(),
-                NodeColor::Black,
-            );
-            let parent_color: NodeColor = Self::_return_color(parent.as_ref().unwrap());
-            Self::_change_color(&mut sibling.as_ref().unwrap(), parent_color);
-            self._left_rotate(parent.as_ref().unwrap());
-            Self::_change_color(&mut parent.as_ref().unwrap(), NodeColor::Black);
+            Self::_change_color(&mut sibling_right.as_ref().ok_or(TreeError::MissingChild)?, NodeColor::Black);
+            let parent_color: NodeColor = Self::_return_color(&parent);
+            Self::_change_color(&mut &sibling, parent_color);
+            self._left_rotate(&parent);
+            Self::_change_color(&mut &parent, NodeColor::Black);
 
-            return;
+            return Ok(());
         }
-        Self::_change_color(&mut sibling.as_ref().unwrap(), NodeColor::Black);
-        Self::_change_color(&mut parent.as_ref().unwrap(), NodeColor::Red);
+        Self::_change_color(&mut &sibling, NodeColor::Black);
+        Self::_change_color(&mut &parent, NodeColor::Red);
         if Self::_is_left_child(node) {
-            self._left_rotate(parent.as_ref().unwrap());
+            self._left_rotate(&parent);
         } else {
-            self._right_rotate(parent.as_ref().unwrap());
+            self._right_rotate(&parent);
+        }
+        self._delete_repair(node)
+    }
+
+    fn _size(node: &OptionNode<K>) -> usize {
+        node.as_ref().map_or(0, |inner_node| inner_node.borrow().size)
+    }
+
+    /// Recomputes the cached subtree size, total count, height, black height
+    /// and leaf count of `node` from its children.
+    fn _update(node: &RcRefcellRBTNode<K>) {
+        let (mut size, mut height, mut leaves) = (1, 0, 0);
+        let mut total = node.borrow().count;
+        let mut black_height = 1;
+        {
+            let this_node = node.borrow();
+            for child in [&this_node.left, &this_node.right].into_iter().flatten() {
+                let child = child.borrow();
+                size += child.size;
+                total += child.total;
+                height = max(height, child.height);
+                leaves += child.leaves;
+            }
+            if let Some(left) = &this_node.left {
+                let left = left.borrow();
+                black_height = left.black_height + u32::from(left.color == NodeColor::Black);
+            }
+        }
+        let mut this_node = node.borrow_mut();
+        this_node.size = size;
+        this_node.total = total;
+        this_node.height = height + 1;
+        this_node.black_height = black_height;
+        this_node.leaves = max(leaves, 1);
+    }
+
+    fn _update_upward(node: OptionNode<K>) {
+        let mut current = node;
+        while let Some(inner_node) = current {
+            Self::_update(&inner_node);
+            current = inner_node.borrow().get_parent();
         }
-        self._delete_repair(node);
     }
 
-    fn _return_color(node: &RcRefcellRBTNode) -> NodeColor {
+    fn _return_color(node: &RcRefcellRBTNode<K>) -> NodeColor {
         node.borrow().color.clone()
     }
 
-    fn _change_color(node: &mut &RcRefcellRBTNode, color: NodeColor) {
+    fn _change_color(node: &mut &RcRefcellRBTNode<K>, color: NodeColor) {
         node.borrow_mut().color = color;
     }
 
-    fn _return_node_same_level(node: &RcRefcellRBTNode) -> OptionNode {
-        if let Some(parent) = &node.borrow().parent {
+    fn _return_node_same_level(node: &RcRefcellRBTNode<K>) -> OptionNode<K> {
+        if let Some(parent) = node.borrow().get_parent() {
             let parent_node = &parent.borrow();
             return if Self::_is_left_child(node) {
                 parent_node.right.clone()
@@ -386,32 +842,104 @@ impl RBTree {
         None
     }
 
-    fn _has_red_child(node: &RcRefcellRBTNode) -> bool {
+    fn _has_red_child(node: &RcRefcellRBTNode<K>) -> bool {
         let left_red = node
             .borrow()
             .left
             .as_ref()
-            .map_or(false, |left| Self::_return_color(left) == NodeColor::Red);
+            .is_some_and(|left| Self::_return_color(left) == NodeColor::Red);
         let right_red = node
             .borrow()
             .right
             .as_ref()
-            .map_or(false, |right| Self::_return_color(right) == NodeColor::Red);
+            .is_some_and(|right| Self::_return_color(right) == NodeColor::Red);
 
         left_red || right_red
     }
 }
 
+impl<K: Ord + Clone + Debug> Display for RBTree<K> {
+    /// Draws the tree in `RenderStyle::Unicode`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render(f, RenderStyle::Unicode)
+    }
+}
+
+impl_set_operators!(RBTree);
+
+impl<K: Ord + Clone + Debug> FromIterator<K> for RBTree<K> {
+    /// Sorts the keys and builds the tree in one pass, without rotations.
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut keys: Vec<K> = iter.into_iter().collect();
+        keys.sort();
+        keys.dedup();
+        Self::from_sorted_vec(keys)
+    }
+}
+
+impl<K: Ord + Clone + Debug> Extend<K> for RBTree<K> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+impl<'a, K: Ord + Clone + Debug> IntoIterator for &'a RBTree<K> {
+    type Item = K;
+    type IntoIter = Iter<'a, K, RBTree<K>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
 
 
 #[cfg(test)]
 mod test {
+    use std::cell::Cell;
+
+    use crate::error::TreeError;
+    use crate::invariant::{InvariantViolation, Rule};
+    use crate::rbnode::NodeColor;
     use crate::rbtree;
     use crate::tree::Tree;
 
+    thread_local! {
+        static LIVE_KEYS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Key that counts how many copies of itself are alive, so a leaked node
+    /// shows up as a key that is never dropped.
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct CountedKey(i64);
+
+    impl CountedKey {
+        fn new(key: i64) -> Self {
+            LIVE_KEYS.with(|live| live.set(live.get() + 1));
+            CountedKey(key)
+        }
+    }
+
+    impl Clone for CountedKey {
+        fn clone(&self) -> Self {
+            CountedKey::new(self.0)
+        }
+    }
+
+    impl Drop for CountedKey {
+        fn drop(&mut self) {
+            LIVE_KEYS.with(|live| live.set(live.get() - 1));
+        }
+    }
+
+    fn live_keys() -> usize {
+        LIVE_KEYS.with(|live| live.get())
+    }
+
     #[test]
     fn test_rbtree() {
-        let mut rb_tree: rbtree::RBTree = rbtree::RBTree::new();
+        let mut rb_tree: rbtree::RBTree<i64> = rbtree::RBTree::new();
         let input = vec![9, 5, 15, 11, 19, 10, 14, 20, 13, 12, 18, 25, 6, 16, 4, 3, 2, 1, 7, 8];
         let mut sorted_input = input.clone();
         sorted_input.sort();
@@ -427,53 +955,196 @@ mod test {
 
         // Initial checks
         assert_eq!(rb_tree.get_height(), 0);
-        assert_eq!(rb_tree.is_empty(), true);
+        assert!(rb_tree.is_empty());
         assert_eq!(rb_tree.count_nodes(), 0);
         // Insert items
         for number in input_slice {
             rb_tree.insert(*number);
+            rb_tree.validate().unwrap();
         }
         // Check tree properties
         assert_eq!(rb_tree.count_nodes(), input_slice.len().try_into().unwrap());
         assert_eq!(rb_tree.get_min().unwrap(), *input_slice.iter().min().unwrap());
         assert_eq!(rb_tree.get_max().unwrap(), *input_slice.iter().max().unwrap());
-        assert_eq!(rb_tree.is_empty(), false);
+        assert!(!rb_tree.is_empty());
         assert_eq!(rb_tree.get_height(), 5);
+        assert_eq!(rb_tree.black_height(), 3);
         assert_eq!(rb_tree.count_leaves(), 9);
         assert_eq!(rb_tree.in_order_traversal(), sorted_input);
         rb_tree.print_tree();
 
         // Check if items are in the tree
         for number in input_slice {
-            assert_eq!(rb_tree.contain(*number), true);
+            assert!(rb_tree.contain(number));
         }
         // Delete items
         for number in to_delete_slice {
-            rb_tree.delete(*number);
+            rb_tree.delete(number);
+            rb_tree.validate().unwrap();
         }
         // Check tree properties after deletion
         assert_eq!(rb_tree.count_nodes(), remaining_slice.len().try_into().unwrap());
         assert_eq!(rb_tree.get_min().unwrap(), *remaining_slice.iter().min().unwrap());
         assert_eq!(rb_tree.get_max().unwrap(), *remaining_slice.iter().max().unwrap());
-        assert_eq!(rb_tree.is_empty(), false);
+        assert!(!rb_tree.is_empty());
         assert_eq!(rb_tree.get_height(), 4);
         assert_eq!(rb_tree.count_leaves(), 4);
         
         // Check if items are not in the tree anymore
         for number in to_delete_slice {
-            assert_eq!(rb_tree.contain(*number), false);
+            assert!(!rb_tree.contain(number));
         }
         // Check if other items are still in the tree
         for number in remaining_slice {
-            assert_eq!(rb_tree.contain(*number), true);
+            assert!(rb_tree.contain(number));
         }
         // Delete all items
         for number in input_slice {
-            rb_tree.delete(*number);
+            rb_tree.delete(number);
+            rb_tree.validate().unwrap();
         }
         // Check tree properties after deletion
         assert_eq!(rb_tree.get_height(), 0);
-        assert_eq!(rb_tree.is_empty(), true);
+        assert!(rb_tree.is_empty());
         assert_eq!(rb_tree.count_nodes(), 0);
     }
+    #[test]
+    fn test_rbtree_string_keys() {
+        let mut rb_tree: rbtree::RBTree<String> = rbtree::RBTree::new();
+        for word in ["pear", "apple", "fig", "banana", "cherry"] {
+            rb_tree.insert(word.to_string());
+            rb_tree.validate().unwrap();
+        }
+        assert!(!rb_tree.insert("apple".to_string()));
+        assert_eq!(rb_tree.delete(&"fig".to_string()), Some("fig".to_string()));
+        assert_eq!(rb_tree.delete(&"fig".to_string()), None);
+        rb_tree.validate().unwrap();
+
+        assert_eq!(rb_tree.get_min().unwrap(), "apple");
+        assert_eq!(rb_tree.get_max().unwrap(), "pear");
+        assert!(rb_tree.contain(&"cherry".to_string()));
+        assert!(!rb_tree.contain(&"fig".to_string()));
+        assert_eq!(rb_tree.in_order_traversal(), vec!["apple", "banana", "cherry", "pear"]);
+    }
+    #[test]
+    fn test_rbtree_frees_nodes() {
+        let mut rb_tree: rbtree::RBTree<CountedKey> = rbtree::RBTree::new();
+        for number in 0..200 {
+            rb_tree.insert(CountedKey::new((number * 37) % 200));
+            rb_tree.validate().unwrap();
+        }
+        assert_eq!(live_keys(), 200);
+
+        for number in (0..200).step_by(3) {
+            rb_tree.delete(&CountedKey::new(number));
+            rb_tree.validate().unwrap();
+        }
+        assert_eq!(rb_tree.len(), 133);
+        assert_eq!(live_keys(), 133);
+
+        drop(rb_tree);
+        assert_eq!(live_keys(), 0);
+    }
+    #[test]
+    fn test_rbtree_validate_reports_violations() {
+        let mut rb_tree: rbtree::RBTree<i64> = rbtree::RBTree::new();
+        for number in 1..=10 {
+            rb_tree.insert(number);
+        }
+        assert_eq!(rb_tree.validate(), Ok(()));
+
+        let (_, node) = rb_tree.search(&9);
+        let node = node.unwrap();
+        node.borrow_mut().color = NodeColor::Red;
+        assert_eq!(rb_tree.validate(), Err(InvariantViolation::new(9, Rule::RedRedEdge)));
+
+        node.borrow_mut().color = NodeColor::Black;
+        let root = rb_tree.get_root().unwrap();
+        root.borrow_mut().color = NodeColor::Red;
+        let violation = rb_tree.validate().unwrap_err();
+        assert_eq!(violation.rule, Rule::RedRoot);
+        assert_eq!(violation.to_string(), "key 4: root is red");
+
+        root.borrow_mut().color = NodeColor::Black;
+        node.borrow_mut().set_parent(&None);
+        assert_eq!(rb_tree.validate(), Err(InvariantViolation::new(9, Rule::ParentLink)));
+    }
+
+    #[test]
+    fn test_rbtree_try_delete_reports_corruption() {
+        let mut rb_tree: rbtree::RBTree<i64> = (1..=3).collect();
+        assert_eq!(rb_tree.try_delete(&2), Ok(Some(2)));
+        assert_eq!(rb_tree.try_delete(&2), Ok(None));
+
+        // A black leaf without a sibling breaks the black-height rule.
+        let leaf = rbtree::RBTree::join_node(1, NodeColor::Black, None, None);
+        let root = rbtree::RBTree::join_node(2, NodeColor::Black, Some(leaf), None);
+        let mut rb_tree = rbtree::RBTree::from_root(Some(root));
+        assert_eq!(rb_tree.try_delete(&1), Err(TreeError::MissingSibling));
+        assert_eq!(TreeError::MissingSibling.to_string(), "node has no sibling");
+    }
+    #[test]
+    fn test_rbtree_from_sorted_iter() {
+        for size in 0..64 {
+            let rb_tree = rbtree::RBTree::from_sorted_iter((0..size).flat_map(|key| [key, key]));
+            rb_tree.validate().unwrap();
+            assert_eq!(rb_tree.in_order_traversal(), (0..size).collect::<Vec<i64>>());
+        }
+        let rb_tree = rbtree::RBTree::from_sorted_iter(1..=6);
+        assert_eq!(rb_tree.pre_order_traversal(), vec![4, 2, 1, 3, 6, 5]);
+        assert_eq!(
+            rb_tree.to_string(),
+            "4:B\n├── 2:B\n│   ├── 1:R\n│   └── 3:R\n└── 6:B\n    ├── 5:R\n    └── ∅\n"
+        );
+
+        let mut rb_tree: rbtree::RBTree<i64> = [9, 2, 7, 2].into_iter().collect();
+        rb_tree.extend(0..4);
+        rb_tree.validate().unwrap();
+        assert_eq!(rb_tree.in_order_traversal(), vec![0, 1, 2, 3, 7, 9]);
+    }
+
+    #[test]
+    #[should_panic(expected = "RBTree::from_sorted_iter: keys are not in ascending order")]
+    fn test_rbtree_from_sorted_iter_rejects_unsorted() {
+        rbtree::RBTree::from_sorted_iter([1, 3, 2]);
+    }
+    #[test]
+    fn test_rbtree_split_join() {
+        let build = |size: i64| {
+            let mut tree: rbtree::RBTree<i64> = rbtree::RBTree::new();
+            for number in 0..size {
+                tree.insert(number * 919 % 1000);
+            }
+            tree
+        };
+        for size in 0..60 {
+            let all = build(size).in_order_traversal();
+            for key in [0, 250, 500, 999, 1000] {
+                let mut rb_tree = build(size);
+                let mut right = rb_tree.split_off(&key);
+                rb_tree.validate().unwrap();
+                right.validate().unwrap();
+                assert!(rb_tree.iter().all(|number| number < key) && right.iter().all(|number| number >= key));
+                assert_eq!(rb_tree.len() + right.len(), all.len());
+
+                if let Some(pivot) = right.get_min() {
+                    right.delete(&pivot);
+                    let joined = rbtree::RBTree::join(rb_tree, pivot, right);
+                    joined.validate().unwrap();
+                    assert_eq!(joined.in_order_traversal(), all);
+                }
+            }
+        }
+
+        let mut rb_tree = build(40);
+        let mut other: rbtree::RBTree<i64> = (500..700).collect();
+        rb_tree.append(&mut other);
+        rb_tree.validate().unwrap();
+        assert!(other.is_empty());
+        let mut expected = build(40).in_order_traversal();
+        expected.extend(500..700);
+        expected.sort();
+        expected.dedup();
+        assert_eq!(rb_tree.in_order_traversal(), expected);
+    }
 }
@@ -0,0 +1,221 @@
+use std::fmt::{self, Debug};
+
+use crate::rbnode::NodeColor;
+use crate::tree::Tree;
+
+/// Layout used by `Tree::render`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderStyle {
+    /// Root on the left edge, left subtree above it; what `print_tree` shows.
+    Sideways,
+    /// Root centered on the first line, children joined by `/` and `\`.
+    TopDown,
+    /// One node per line below its parent, like `tree(1)`.
+    Unicode,
+}
+
+/// Labels red-black nodes with their key and an `R` or `B` color mark.
+pub(crate) fn color_label<K: Debug>(key: &K, color: &NodeColor) -> String {
+    let mark = match color {
+        NodeColor::Red => 'R',
+        NodeColor::Black => 'B',
+    };
+    format!("{:?}:{}", key, mark)
+}
+
+pub(crate) fn write_top_down<K, T>(tree: &T, out: &mut dyn fmt::Write) -> fmt::Result
+where
+    K: Ord + Clone + Debug,
+    T: Tree<K>,
+{
+    let Some(root) = tree.get_root() else {
+        return Ok(());
+    };
+    for line in _layout(tree, &root).lines {
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_unicode<K, T>(tree: &T, out: &mut dyn fmt::Write) -> fmt::Result
+where
+    K: Ord + Clone + Debug,
+    T: Tree<K>,
+{
+    let Some(root) = tree.get_root() else {
+        return Ok(());
+    };
+    writeln!(out, "{}", tree.render_label(&root))?;
+    _write_unicode_children(tree, out, &root, "")
+}
+
+/// A rendered subtree: equally wide lines and the column its root label is
+/// centered on.
+struct Block {
+    lines: Vec<String>,
+    width: usize,
+    middle: usize,
+}
+
+fn _layout<K, T>(tree: &T, node: &T::NodeRef) -> Block
+where
+    K: Ord + Clone + Debug,
+    T: Tree<K>,
+{
+    let label = tree.render_label(node);
+    let label_width = label.chars().count();
+    let left = tree.get_left(node).map(|left| _layout(tree, &left));
+    let right = tree.get_right(node).map(|right| _layout(tree, &right));
+
+    match (left, right) {
+        (None, None) => Block {
+            lines: vec![label],
+            width: label_width,
+            middle: label_width / 2,
+        },
+        (Some(left), None) => {
+            let mut lines = vec![
+                format!("{}{}{}", " ".repeat(left.middle + 1), "_".repeat(left.width - left.middle - 1), label),
+                format!("{}/{}", " ".repeat(left.middle), " ".repeat(left.width - left.middle - 1 + label_width)),
+            ];
+            lines.extend(left.lines.into_iter().map(|line| line + &" ".repeat(label_width)));
+            Block {
+                lines,
+                width: left.width + label_width,
+                middle: left.width + label_width / 2,
+            }
+        }
+        (None, Some(right)) => {
+            let mut lines = vec![
+                format!("{}{}{}", label, "_".repeat(right.middle), " ".repeat(right.width - right.middle)),
+                format!("{}\\{}", " ".repeat(label_width + right.middle), " ".repeat(right.width - right.middle - 1)),
+            ];
+            lines.extend(right.lines.into_iter().map(|line| " ".repeat(label_width) + &line));
+            Block {
+                lines,
+                width: right.width + label_width,
+                middle: label_width / 2,
+            }
+        }
+        (Some(left), Some(right)) => {
+            let mut lines = vec![
+                format!(
+                    "{}{}{}{}{}",
+                    " ".repeat(left.middle + 1),
+                    "_".repeat(left.width - left.middle - 1),
+                    label,
+                    "_".repeat(right.middle),
+                    " ".repeat(right.width - right.middle)
+                ),
+                format!(
+                    "{}/{}\\{}",
+                    " ".repeat(left.middle),
+                    " ".repeat(left.width - left.middle - 1 + label_width + right.middle),
+                    " ".repeat(right.width - right.middle - 1)
+                ),
+            ];
+            let depth = left.lines.len().max(right.lines.len());
+            for row in 0..depth {
+                let left_line = left.lines.get(row).cloned().unwrap_or_else(|| " ".repeat(left.width));
+                let right_line = right.lines.get(row).cloned().unwrap_or_else(|| " ".repeat(right.width));
+                lines.push(format!("{}{}{}", left_line, " ".repeat(label_width), right_line));
+            }
+            Block {
+                lines,
+                width: left.width + label_width + right.width,
+                middle: left.width + label_width / 2,
+            }
+        }
+    }
+}
+
+/// Writes the children of `node`, left first. A node with a single child
+/// shows `∅` for the missing one so the sides stay apart.
+fn _write_unicode_children<K, T>(tree: &T, out: &mut dyn fmt::Write, node: &T::NodeRef, prefix: &str) -> fmt::Result
+where
+    K: Ord + Clone + Debug,
+    T: Tree<K>,
+{
+    let (left, right) = (tree.get_left(node), tree.get_right(node));
+    if left.is_none() && right.is_none() {
+        return Ok(());
+    }
+    for (child, is_last) in [(left, false), (right, true)] {
+        let (branch, indent) = if is_last { ("└── ", "    ") } else { ("├── ", "│   ") };
+        match child {
+            Some(child) => {
+                writeln!(out, "{}{}{}", prefix, branch, tree.render_label(&child))?;
+                _write_unicode_children(tree, out, &child, &format!("{}{}", prefix, indent))?;
+            }
+            None => writeln!(out, "{}{}∅", prefix, branch)?,
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::avltree::AVLTree;
+    use crate::rbtree::RBTree;
+    use crate::render::RenderStyle;
+    use crate::tree::Tree;
+
+    fn render<T: Tree<i64>>(tree: &T, style: RenderStyle) -> String {
+        let mut out = String::new();
+        tree.render(&mut out, style).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_render_styles() {
+        let mut rb_tree: RBTree<i64> = RBTree::new();
+        assert_eq!(render(&rb_tree, RenderStyle::TopDown), "");
+        for number in [5, 3, 8, 1, 4, 9] {
+            rb_tree.insert(number);
+        }
+        assert_eq!(
+            render(&rb_tree, RenderStyle::Sideways),
+            "\n\
+             \x20           L 1 Red\n\
+             \x20     L 3 Black\n\
+             \x20     |     R 4 Red\n\
+             Root 5 Black\n\
+             \x20     R 8 Black\n\
+             \x20           R 9 Red\n"
+        );
+        assert_eq!(
+            render(&rb_tree, RenderStyle::TopDown),
+            "     ____5:B_\n\
+             \x20   /        \\\n\
+             \x20 _3:B_     8:B_\n\
+             \x20/     \\        \\\n\
+             1:R   4:R      9:R\n"
+        );
+        assert_eq!(
+            render(&rb_tree, RenderStyle::Unicode),
+            "5:B\n\
+             ├── 3:B\n\
+             │   ├── 1:R\n\
+             │   └── 4:R\n\
+             └── 8:B\n\
+             \x20   ├── ∅\n\
+             \x20   └── 9:R\n"
+        );
+
+        let mut avl_tree: AVLTree<i64> = AVLTree::new();
+        for number in [2, 1, 3, 4] {
+            avl_tree.insert(number);
+        }
+        assert_eq!(
+            render(&avl_tree, RenderStyle::Sideways),
+            "\n\
+             \x20          L 1\n\
+             Root 2----|\n\
+             \x20          R 3----|\n\
+             \x20                 R 4\n"
+        );
+        assert_eq!(render(&avl_tree, RenderStyle::TopDown), " 2\n/ \\\n1 3\n   \\\n   4\n");
+        assert_eq!(avl_tree.to_string(), render(&avl_tree, RenderStyle::Unicode));
+    }
+}
@@ -0,0 +1,183 @@
+//! Serde support, behind the `serde` feature.
+//!
+//! `RBTree` and `AVLTree` serialize as a sorted sequence of keys and are
+//! rebuilt from it in O(n). The `exact` module keeps the exact
+//! node layout instead, with colors or heights, for use with
+//! `#[serde(with = "tree::serialize::exact")]`.
+use std::fmt::Debug;
+
+use serde::de::Error;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::avlnode::{AVLNode, RcRefcellAVLNode};
+use crate::avltree::AVLTree;
+use crate::invariant::{InvariantViolation, Rule};
+use crate::node::Node;
+use crate::rbnode::{NodeColor, OptionNode as RBOptionNode, RcRefcellRBTNode};
+use crate::rbtree::RBTree;
+use crate::tree::Tree;
+
+impl<K: Ord + Clone + Debug + Serialize> Serialize for RBTree<K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, K: Ord + Clone + Debug + Deserialize<'de>> Deserialize<'de> for RBTree<K> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(RBTree::from_sorted_vec(_sorted_keys(deserializer)?))
+    }
+}
+
+impl<K: Ord + Clone + Debug + Serialize> Serialize for AVLTree<K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, K: Ord + Clone + Debug + Deserialize<'de>> Deserialize<'de> for AVLTree<K> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(AVLTree::from_sorted_vec(_sorted_keys(deserializer)?))
+    }
+}
+
+/// Reads a sequence of keys, rejecting it unless strictly ascending.
+fn _sorted_keys<'de, K, D>(deserializer: D) -> Result<Vec<K>, D::Error>
+where
+    K: Ord + Debug + Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    let keys = Vec::<K>::deserialize(deserializer)?;
+    if let Some(pair) = keys.windows(2).find(|pair| pair[0] >= pair[1]) {
+        return Err(D::Error::custom(format!("key {:?}: {}", pair[1], Rule::KeyOrder)));
+    }
+    Ok(keys)
+}
+
+/// Trees that can be written node by node.
+pub trait SerializeShape {
+    fn serialize_shape<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+}
+
+/// Trees that can be read back node by node. Split from `SerializeShape`
+/// so that keys which only serialize can still be written.
+pub trait DeserializeShape<'de>: Sized {
+    /// Fails if the loaded tree breaks any of its invariants.
+    fn deserialize_shape<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+/// Exact-shape form: every node as `{key, color|height, left, right}`.
+pub mod exact {
+    use serde::{Deserializer, Serializer};
+
+    use super::{DeserializeShape, SerializeShape};
+
+    pub fn serialize<T: SerializeShape, S: Serializer>(tree: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        tree.serialize_shape(serializer)
+    }
+
+    pub fn deserialize<'de, T: DeserializeShape<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        T::deserialize_shape(deserializer)
+    }
+}
+
+/// Borrowed view of a red-black subtree, serialized as an `RBShape`.
+struct RBNodeView<'a, K>(&'a RcRefcellRBTNode<K>);
+
+impl<K: Serialize> Serialize for RBNodeView<'_, K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let node = self.0.borrow();
+        let mut state = serializer.serialize_struct("RBShape", 4)?;
+        state.serialize_field("key", &node.key)?;
+        state.serialize_field("color", &node.color)?;
+        state.serialize_field("left", &node.left.as_ref().map(RBNodeView))?;
+        state.serialize_field("right", &node.right.as_ref().map(RBNodeView))?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct RBShape<K> {
+    key: K,
+    color: NodeColor,
+    left: Option<Box<RBShape<K>>>,
+    right: Option<Box<RBShape<K>>>,
+}
+
+impl<K: Ord + Clone + Debug> RBShape<K> {
+    fn build(self) -> RcRefcellRBTNode<K> {
+        let left = self.left.map(|left| left.build());
+        let right = self.right.map(|right| right.build());
+        RBTree::join_node(self.key, self.color, left, right)
+    }
+}
+
+impl<K: Ord + Clone + Debug + Serialize> SerializeShape for RBTree<K> {
+    fn serialize_shape<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get_root().as_ref().map(RBNodeView).serialize(serializer)
+    }
+}
+
+impl<'de, K: Ord + Clone + Debug + Deserialize<'de>> DeserializeShape<'de> for RBTree<K> {
+    fn deserialize_shape<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let root: RBOptionNode<K> = Option::<RBShape<K>>::deserialize(deserializer)?.map(RBShape::build);
+        let tree = RBTree::from_root(root);
+        tree.validate().map_err(D::Error::custom)?;
+        Ok(tree)
+    }
+}
+
+/// Borrowed view of an AVL subtree, serialized as an `AVLShape`.
+struct AVLNodeView<'a, K>(&'a RcRefcellAVLNode<K>);
+
+impl<K: Ord + Clone + Debug + Serialize> Serialize for AVLNodeView<'_, K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let node = self.0.borrow();
+        let mut state = serializer.serialize_struct("AVLShape", 4)?;
+        state.serialize_field("key", node.get_key())?;
+        state.serialize_field("height", &node.get_height())?;
+        state.serialize_field("left", &node.get_left().as_ref().map(AVLNodeView))?;
+        state.serialize_field("right", &node.get_right().as_ref().map(AVLNodeView))?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct AVLShape<K> {
+    key: K,
+    height: u32,
+    left: Option<Box<AVLShape<K>>>,
+    right: Option<Box<AVLShape<K>>>,
+}
+
+impl<K: Ord + Clone + Debug> AVLShape<K> {
+    /// Fails if a stored height differs from the one the shape implies.
+    fn build(self) -> Result<RcRefcellAVLNode<K>, InvariantViolation<K>> {
+        let left = self.left.map(|left| left.build()).transpose()?;
+        let right = self.right.map(|right| right.build()).transpose()?;
+        let node = AVLNode::join(self.key, left, right);
+        if node.borrow().get_height() != self.height {
+            return Err(InvariantViolation::new(node.borrow().key.clone(), Rule::StoredHeight));
+        }
+        Ok(node)
+    }
+}
+
+impl<K: Ord + Clone + Debug + Serialize> SerializeShape for AVLTree<K> {
+    fn serialize_shape<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get_root().as_ref().map(AVLNodeView).serialize(serializer)
+    }
+}
+
+impl<'de, K: Ord + Clone + Debug + Deserialize<'de>> DeserializeShape<'de> for AVLTree<K> {
+    fn deserialize_shape<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let root = Option::<AVLShape<K>>::deserialize(deserializer)?
+            .map(AVLShape::build)
+            .transpose()
+            .map_err(D::Error::custom)?;
+        let tree = AVLTree::from_root(root);
+        tree.validate().map_err(D::Error::custom)?;
+        Ok(tree)
+    }
+}
@@ -0,0 +1,236 @@
+use std::cmp::Ordering;
+use std::iter::Peekable;
+
+use crate::iter::Iter;
+use crate::tree::Tree;
+
+/// Walks two trees in order side by side.
+struct Merge<'a, K, T: Tree<K>>
+where
+    K: Ord + Clone,
+{
+    first: Peekable<Iter<'a, K, T>>,
+    second: Peekable<Iter<'a, K, T>>,
+}
+
+impl<'a, K: Ord + Clone, T: Tree<K>> Merge<'a, K, T> {
+    fn new(first: &'a T, second: &'a T) -> Self {
+        Merge {
+            first: first.iter().peekable(),
+            second: second.iter().peekable(),
+        }
+    }
+
+    /// Compares the next keys of both trees, treating an exhausted tree as
+    /// greater than any key. `None` once both are exhausted.
+    fn peek_ordering(&mut self) -> Option<Ordering> {
+        match (self.first.peek(), self.second.peek()) {
+            (Some(first), Some(second)) => Some(first.cmp(second)),
+            (Some(_), None) => Some(Ordering::Less),
+            (None, Some(_)) => Some(Ordering::Greater),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Keys in either tree, from `Tree::union`.
+pub struct Union<'a, K, T: Tree<K>>(Merge<'a, K, T>)
+where
+    K: Ord + Clone;
+
+/// Keys in both trees, from `Tree::intersection`.
+pub struct Intersection<'a, K, T: Tree<K>>(Merge<'a, K, T>)
+where
+    K: Ord + Clone;
+
+/// Keys in the first tree but not the second, from `Tree::difference`.
+pub struct Difference<'a, K, T: Tree<K>>(Merge<'a, K, T>)
+where
+    K: Ord + Clone;
+
+/// Keys in exactly one of the trees, from `Tree::symmetric_difference`.
+pub struct SymmetricDifference<'a, K, T: Tree<K>>(Merge<'a, K, T>)
+where
+    K: Ord + Clone;
+
+impl<'a, K: Ord + Clone, T: Tree<K>> Union<'a, K, T> {
+    pub(crate) fn new(first: &'a T, second: &'a T) -> Self {
+        Union(Merge::new(first, second))
+    }
+}
+
+impl<'a, K: Ord + Clone, T: Tree<K>> Intersection<'a, K, T> {
+    pub(crate) fn new(first: &'a T, second: &'a T) -> Self {
+        Intersection(Merge::new(first, second))
+    }
+}
+
+impl<'a, K: Ord + Clone, T: Tree<K>> Difference<'a, K, T> {
+    pub(crate) fn new(first: &'a T, second: &'a T) -> Self {
+        Difference(Merge::new(first, second))
+    }
+}
+
+impl<'a, K: Ord + Clone, T: Tree<K>> SymmetricDifference<'a, K, T> {
+    pub(crate) fn new(first: &'a T, second: &'a T) -> Self {
+        SymmetricDifference(Merge::new(first, second))
+    }
+}
+
+impl<K: Ord + Clone, T: Tree<K>> Iterator for Union<'_, K, T> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let merge = &mut self.0;
+        match merge.peek_ordering()? {
+            Ordering::Less => merge.first.next(),
+            Ordering::Greater => merge.second.next(),
+            Ordering::Equal => {
+                merge.second.next();
+                merge.first.next()
+            }
+        }
+    }
+}
+
+impl<K: Ord + Clone, T: Tree<K>> Iterator for Intersection<'_, K, T> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let merge = &mut self.0;
+        loop {
+            merge.first.peek()?;
+            merge.second.peek()?;
+            match merge.peek_ordering()? {
+                Ordering::Less => merge.first.next(),
+                Ordering::Greater => merge.second.next(),
+                Ordering::Equal => {
+                    merge.second.next();
+                    return merge.first.next();
+                }
+            };
+        }
+    }
+}
+
+impl<K: Ord + Clone, T: Tree<K>> Iterator for Difference<'_, K, T> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let merge = &mut self.0;
+        loop {
+            merge.first.peek()?;
+            match merge.peek_ordering()? {
+                Ordering::Less => return merge.first.next(),
+                Ordering::Greater => merge.second.next(),
+                Ordering::Equal => {
+                    merge.second.next();
+                    merge.first.next()
+                }
+            };
+        }
+    }
+}
+
+impl<K: Ord + Clone, T: Tree<K>> Iterator for SymmetricDifference<'_, K, T> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let merge = &mut self.0;
+        loop {
+            match merge.peek_ordering()? {
+                Ordering::Less => return merge.first.next(),
+                Ordering::Greater => return merge.second.next(),
+                Ordering::Equal => {
+                    merge.first.next();
+                    merge.second.next();
+                }
+            }
+        }
+    }
+}
+
+/// Implements `|`, `&`, `-` and `^` on references to `$tree`, building the
+/// result in O(n + m) from the merged keys instead of inserting them.
+macro_rules! impl_set_operators {
+    ($tree:ident) => {
+        impl<K: Ord + Clone + std::fmt::Debug> std::ops::BitOr for &$tree<K> {
+            type Output = $tree<K>;
+
+            fn bitor(self, other: &$tree<K>) -> $tree<K> {
+                $tree::from_sorted_vec(self.union(other).collect())
+            }
+        }
+
+        impl<K: Ord + Clone + std::fmt::Debug> std::ops::BitAnd for &$tree<K> {
+            type Output = $tree<K>;
+
+            fn bitand(self, other: &$tree<K>) -> $tree<K> {
+                $tree::from_sorted_vec(self.intersection(other).collect())
+            }
+        }
+
+        impl<K: Ord + Clone + std::fmt::Debug> std::ops::Sub for &$tree<K> {
+            type Output = $tree<K>;
+
+            fn sub(self, other: &$tree<K>) -> $tree<K> {
+                $tree::from_sorted_vec(self.difference(other).collect())
+            }
+        }
+
+        impl<K: Ord + Clone + std::fmt::Debug> std::ops::BitXor for &$tree<K> {
+            type Output = $tree<K>;
+
+            fn bitxor(self, other: &$tree<K>) -> $tree<K> {
+                $tree::from_sorted_vec(self.symmetric_difference(other).collect())
+            }
+        }
+    };
+}
+
+pub(crate) use impl_set_operators;
+
+
+#[cfg(test)]
+mod test {
+    use crate::avltree::AVLTree;
+    use crate::rbtree::RBTree;
+    use crate::tree::Tree;
+
+    #[test]
+    fn test_set_operations() {
+        let evens: RBTree<i64> = (0..20).step_by(2).collect();
+        let threes: RBTree<i64> = (0..20).step_by(3).collect();
+        assert_eq!(
+            evens.union(&threes).collect::<Vec<_>>(),
+            vec![0, 2, 3, 4, 6, 8, 9, 10, 12, 14, 15, 16, 18]
+        );
+        assert_eq!(evens.intersection(&threes).collect::<Vec<_>>(), vec![0, 6, 12, 18]);
+        assert_eq!(evens.difference(&threes).collect::<Vec<_>>(), vec![2, 4, 8, 10, 14, 16]);
+        assert_eq!(threes.difference(&evens).collect::<Vec<_>>(), vec![3, 9, 15]);
+        assert_eq!(evens.symmetric_difference(&threes).collect::<Vec<_>>(), vec![2, 3, 4, 8, 9, 10, 14, 15, 16]);
+
+        for (result, expected) in [
+            (&evens | &threes, evens.union(&threes).collect::<Vec<_>>()),
+            (&evens & &threes, evens.intersection(&threes).collect()),
+            (&evens - &threes, evens.difference(&threes).collect()),
+            (&evens ^ &threes, evens.symmetric_difference(&threes).collect()),
+        ] {
+            result.validate().unwrap();
+            assert_eq!(result.in_order_traversal(), expected);
+        }
+
+        let sixes: AVLTree<i64> = (0..20).step_by(6).collect();
+        let evens: AVLTree<i64> = (0..20).step_by(2).collect();
+        let odds: AVLTree<i64> = (1..20).step_by(2).collect();
+        let empty: AVLTree<i64> = AVLTree::new();
+        assert!(sixes.is_subset(&evens) && evens.is_superset(&sixes));
+        assert!(!evens.is_subset(&sixes) && !sixes.is_subset(&odds));
+        assert!(evens.is_disjoint(&odds) && !evens.is_disjoint(&sixes));
+        assert!(empty.is_subset(&odds) && empty.is_disjoint(&empty));
+        let all = &evens | &odds;
+        all.validate().unwrap();
+        assert_eq!(all.in_order_traversal(), (0..20).collect::<Vec<_>>());
+        assert_eq!((&all - &odds).in_order_traversal(), evens.in_order_traversal());
+    }
+}
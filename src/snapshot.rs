@@ -0,0 +1,365 @@
+//! Binary snapshot files for trees of integer keys.
+//!
+//! Layout, all integers little-endian:
+//!
+//! | bytes | content                                           |
+//! |-------|---------------------------------------------------|
+//! | 4     | magic `TREE`                                      |
+//! | 1     | format version, currently 1                       |
+//! | 1     | tree kind, see `TreeKind`                         |
+//! | 8     | key count                                         |
+//! | ...   | first key, then gaps to each next key, as varints |
+//! | 4     | CRC-32 (IEEE) of everything before it             |
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::avltree::AVLTree;
+use crate::rbtree::RBTree;
+use crate::tree::Tree;
+
+const MAGIC: [u8; 4] = *b"TREE";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 14;
+const TRAILER_LEN: usize = 4;
+
+/// Which tree a snapshot was saved from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TreeKind {
+    RB,
+    AVL,
+}
+
+impl TreeKind {
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(TreeKind::RB),
+            1 => Some(TreeKind::AVL),
+            _ => None,
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            TreeKind::RB => 0,
+            TreeKind::AVL => 1,
+        }
+    }
+}
+
+/// Why a snapshot could not be written or read back.
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnknownKind(u8),
+    WrongKind { expected: TreeKind, found: TreeKind },
+    /// The file is too short to hold a header and a checksum. A file cut
+    /// anywhere later fails its checksum instead.
+    Truncated,
+    /// The checksum matches, but the key section decodes to keys that are
+    /// out of range or not ascending, or to more or fewer keys than the
+    /// header counts.
+    BadKeys,
+    ChecksumMismatch { stored: u32, computed: u32 },
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "snapshot I/O failed: {}", error),
+            SnapshotError::BadMagic => f.write_str("not a tree snapshot"),
+            SnapshotError::UnsupportedVersion(version) => write!(f, "unsupported snapshot version {}", version),
+            SnapshotError::UnknownKind(kind) => write!(f, "unknown tree kind {}", kind),
+            SnapshotError::WrongKind { expected, found } => {
+                write!(f, "snapshot holds an {:?} tree, expected {:?}", found, expected)
+            }
+            SnapshotError::Truncated => f.write_str("snapshot is truncated"),
+            SnapshotError::BadKeys => f.write_str("snapshot keys are corrupted"),
+            SnapshotError::ChecksumMismatch { stored, computed } => {
+                write!(f, "snapshot checksum {:08x} does not match contents ({:08x})", stored, computed)
+            }
+        }
+    }
+}
+
+impl Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SnapshotError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        SnapshotError::Io(error)
+    }
+}
+
+/// Keys a snapshot can store: integers, mapped to `u64` without changing
+/// their order so that consecutive keys differ by a small positive gap.
+pub trait SnapshotKey: Ord + Copy {
+    fn to_u64(self) -> u64;
+
+    fn from_u64(value: u64) -> Option<Self>;
+}
+
+macro_rules! unsigned_snapshot_key {
+    ($($t:ty),*) => {$(
+        impl SnapshotKey for $t {
+            fn to_u64(self) -> u64 {
+                self as u64
+            }
+
+            fn from_u64(value: u64) -> Option<Self> {
+                Self::try_from(value).ok()
+            }
+        }
+    )*};
+}
+
+macro_rules! signed_snapshot_key {
+    ($($t:ty),*) => {$(
+        impl SnapshotKey for $t {
+            /// Flips the sign bit so negative keys sort below positive ones.
+            fn to_u64(self) -> u64 {
+                (self as i64 as u64) ^ (1 << 63)
+            }
+
+            fn from_u64(value: u64) -> Option<Self> {
+                Self::try_from((value ^ (1 << 63)) as i64).ok()
+            }
+        }
+    )*};
+}
+
+unsigned_snapshot_key!(u8, u16, u32, u64, usize);
+signed_snapshot_key!(i8, i16, i32, i64, isize);
+
+impl<K: SnapshotKey + Debug> RBTree<K> {
+    pub fn save_to(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        Ok(fs::write(path, encode(self, TreeKind::RB))?)
+    }
+
+    /// Loads a snapshot saved from an `RBTree`, building it in O(n).
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        Ok(RBTree::from_sorted_vec(decode(&fs::read(path)?, TreeKind::RB)?))
+    }
+}
+
+impl<K: SnapshotKey + Debug> AVLTree<K> {
+    pub fn save_to(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        Ok(fs::write(path, encode(self, TreeKind::AVL))?)
+    }
+
+    /// Loads a snapshot saved from an `AVLTree`, building it in O(n).
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        Ok(AVLTree::from_sorted_vec(decode(&fs::read(path)?, TreeKind::AVL)?))
+    }
+}
+
+fn encode<K, T>(tree: &T, kind: TreeKind) -> Vec<u8>
+where
+    K: SnapshotKey,
+    T: Tree<K>,
+{
+    let mut bytes = Vec::with_capacity(HEADER_LEN + tree.len() * 2 + TRAILER_LEN);
+    bytes.extend_from_slice(&MAGIC);
+    bytes.push(VERSION);
+    bytes.push(kind.to_byte());
+    bytes.extend_from_slice(&(tree.len() as u64).to_le_bytes());
+
+    let mut previous = 0;
+    for key in tree.iter() {
+        let value = key.to_u64();
+        _write_varint(&mut bytes, value - previous);
+        previous = value;
+    }
+    let checksum = crc32(&bytes);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+    bytes
+}
+
+/// Returns the keys of a snapshot in ascending order. The checksum is
+/// verified before any field past the magic is read, so a damaged file is
+/// reported as such rather than as whatever its damage happens to decode to.
+fn decode<K: SnapshotKey>(bytes: &[u8], kind: TreeKind) -> Result<Vec<K>, SnapshotError> {
+    if bytes.len() < HEADER_LEN + TRAILER_LEN {
+        return Err(if bytes.starts_with(&MAGIC) { SnapshotError::Truncated } else { SnapshotError::BadMagic });
+    }
+    if bytes[..4] != MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    let (contents, trailer) = bytes.split_at(bytes.len() - TRAILER_LEN);
+    let stored = u32::from_le_bytes(trailer.try_into().unwrap());
+    let computed = crc32(contents);
+    if stored != computed {
+        return Err(SnapshotError::ChecksumMismatch { stored, computed });
+    }
+
+    if contents[4] != VERSION {
+        return Err(SnapshotError::UnsupportedVersion(contents[4]));
+    }
+    let found = TreeKind::from_byte(contents[5]).ok_or(SnapshotError::UnknownKind(contents[5]))?;
+    if found != kind {
+        return Err(SnapshotError::WrongKind { expected: kind, found });
+    }
+    let count = u64::from_le_bytes(contents[6..HEADER_LEN].try_into().unwrap());
+
+    let mut body = &contents[HEADER_LEN..];
+    // Every key takes at least one byte, which bounds the allocation.
+    let mut keys = Vec::with_capacity(count.min(body.len() as u64) as usize);
+    let mut previous: Option<u64> = None;
+    for _ in 0..count {
+        let gap = _read_varint(&mut body).ok_or(SnapshotError::BadKeys)?;
+        let value = match previous {
+            None => gap,
+            Some(_) if gap == 0 => return Err(SnapshotError::BadKeys),
+            Some(previous) => previous.checked_add(gap).ok_or(SnapshotError::BadKeys)?,
+        };
+        keys.push(K::from_u64(value).ok_or(SnapshotError::BadKeys)?);
+        previous = Some(value);
+    }
+    if !body.is_empty() {
+        return Err(SnapshotError::BadKeys);
+    }
+    Ok(keys)
+}
+
+/// LEB128: seven bits per byte, high bit set on every byte but the last.
+fn _write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Returns None if the bytes run out mid-value or the value overflows.
+fn _read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        // The tenth byte has room for the top bit only.
+        if shift == 63 && byte > 1 {
+            return None;
+        }
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// CRC-32 with the IEEE polynomial, as used by zip and PNG.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::avltree::AVLTree;
+    use crate::rbtree::RBTree;
+    use crate::snapshot::{_read_varint, crc32, decode, encode, SnapshotError, TreeKind};
+    use crate::tree::Tree;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+
+        let mut rb_tree: RBTree<i64> = RBTree::new();
+        for number in [-300, 5, 3, 8, 1, 1 << 40, i64::MIN, i64::MAX] {
+            rb_tree.insert(number);
+        }
+        let path = std::env::temp_dir().join(format!("tree-snapshot-{}.bin", std::process::id()));
+        rb_tree.save_to(&path).unwrap();
+        let loaded = RBTree::<i64>::load_from(&path).unwrap();
+        assert_eq!(loaded.in_order_traversal(), rb_tree.in_order_traversal());
+        loaded.validate().unwrap();
+        assert!(matches!(
+            AVLTree::<i64>::load_from(&path),
+            Err(SnapshotError::WrongKind { expected: TreeKind::AVL, found: TreeKind::RB })
+        ));
+        std::fs::remove_file(&path).unwrap();
+
+        let mut avl_tree: AVLTree<u32> = AVLTree::new();
+        for number in 0..1000 {
+            avl_tree.insert(number * 3);
+        }
+        let bytes = encode(&avl_tree, TreeKind::AVL);
+        assert_eq!(bytes.len(), 14 + 1000 + 4);
+        let keys = decode::<u32>(&bytes, TreeKind::AVL).unwrap();
+        let loaded = AVLTree::from_sorted_vec(keys);
+        assert_eq!(loaded.in_order_traversal(), avl_tree.in_order_traversal());
+        assert_eq!(loaded.get_height(), 10);
+        loaded.validate().unwrap();
+        for len in 0..40 {
+            let built = RBTree::from_sorted_vec((0..len).collect());
+            built.validate().unwrap();
+            assert_eq!(built.len(), len as usize);
+        }
+
+        assert!(matches!(
+            decode::<u32>(&bytes[..bytes.len() - 10], TreeKind::AVL),
+            Err(SnapshotError::ChecksumMismatch { .. })
+        ));
+        assert!(matches!(decode::<u32>(&bytes[..8], TreeKind::AVL), Err(SnapshotError::Truncated)));
+        let mut corrupted = bytes.clone();
+        corrupted[500] ^= 0x01;
+        assert!(matches!(decode::<u32>(&corrupted, TreeKind::AVL), Err(SnapshotError::ChecksumMismatch { .. })));
+        assert!(matches!(decode::<u8>(&bytes, TreeKind::AVL), Err(SnapshotError::BadKeys)));
+        corrupted = bytes.clone();
+        corrupted[0] = b'X';
+        assert!(matches!(decode::<u32>(&corrupted, TreeKind::AVL), Err(SnapshotError::BadMagic)));
+        corrupted = bytes.clone();
+        corrupted[4] = 2;
+        assert!(matches!(decode::<u32>(&corrupted, TreeKind::AVL), Err(SnapshotError::ChecksumMismatch { .. })));
+    }
+
+    #[test]
+    fn test_snapshot_load_rejects_damaged_files() {
+        let tree: AVLTree<u64> = (0..200).map(|number| number * 1000).collect();
+        let path = std::env::temp_dir().join(format!("tree-snapshot-damaged-{}.bin", std::process::id()));
+        tree.save_to(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        // Every gap is 1000, two varint bytes, so this cuts a key in half.
+        let mid_key = 14 + 1 + 2 * 150 + 1;
+        assert!(bytes[mid_key - 1] & 0x80 != 0);
+        std::fs::write(&path, &bytes[..mid_key]).unwrap();
+        assert!(matches!(AVLTree::<u64>::load_from(&path), Err(SnapshotError::ChecksumMismatch { .. })));
+
+        let mut flipped = bytes.clone();
+        flipped[mid_key] ^= 0x40;
+        std::fs::write(&path, &flipped).unwrap();
+        assert!(matches!(AVLTree::<u64>::load_from(&path), Err(SnapshotError::ChecksumMismatch { .. })));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_varint_overflow() {
+        let mut max = vec![0xff; 9];
+        max.push(0x01);
+        assert_eq!(_read_varint(&mut max.as_slice()), Some(u64::MAX));
+        let mut too_long = vec![0xff; 9];
+        too_long.push(0x02);
+        assert_eq!(_read_varint(&mut too_long.as_slice()), None);
+        too_long[9] = 0x81;
+        assert_eq!(_read_varint(&mut too_long.as_slice()), None);
+    }
+}
@@ -1,162 +1,647 @@
-use crate::node::Node;
-use std::cell::RefCell;
-use std::rc::Rc;
+use crate::dot::{self, DotNodeStyle, DotOptions};
+use crate::iter::{Iter, LevelOrderIter, PostOrderIter, PreOrderIter, Range};
+use crate::render::{self, RenderStyle};
+use crate::setops::{Difference, Intersection, SymmetricDifference, Union};
+use std::cmp::Ordering;
+use std::fmt::{self, Debug};
+use std::ops::RangeBounds;
+
+pub trait Tree<K: Ord + Clone> {
+    /// Handle to one node of the tree: an `Rc` for the pointer-based trees,
+    /// an index for the arena-backed ones.
+    type NodeRef: Clone;
 
-pub trait Tree<TN: Node> {
     fn new() -> Self;
 
-    fn get_root(&self) -> &Option<Rc<RefCell<TN>>>;
+    fn get_root(&self) -> Option<Self::NodeRef>;
 
-    fn insert(&mut self, key: i64);
+    fn get_left(&self, node: &Self::NodeRef) -> Option<Self::NodeRef>;
 
-    fn delete(&mut self, key: i64);
+    fn get_right(&self, node: &Self::NodeRef) -> Option<Self::NodeRef>;
 
-    fn print_tree(&self);
+    fn with_key<R>(&self, node: &Self::NodeRef, f: impl FnOnce(&K) -> R) -> R;
 
-    fn get_height(&self) -> u32 {
-        match &self.get_root() {
-            None => 0,
-            Some(node) => node.borrow().get_height(),
+    /// Cached number of nodes in the subtree rooted at `node`.
+    fn get_node_size(&self, node: &Self::NodeRef) -> usize;
+
+    /// Copies of the key held by `node`; always 1 outside a multiset.
+    fn get_node_count(&self, _node: &Self::NodeRef) -> usize {
+        1
+    }
+
+    /// Cached sum of the counts in the subtree rooted at `node`.
+    fn get_node_total(&self, node: &Self::NodeRef) -> usize {
+        self.get_node_size(node)
+    }
+
+    /// Cached height of the subtree rooted at `node`.
+    fn get_node_height(&self, node: &Self::NodeRef) -> u32;
+
+    /// Cached number of leaves in the subtree rooted at `node`.
+    fn count_node_leaves(&self, node: &Self::NodeRef) -> u32;
+
+    /// Inserts `key`, returning `false` if it was already present.
+    fn insert(&mut self, key: K) -> bool;
+
+    /// Removes `key`, returning the stored key if it was present.
+    fn delete<Q>(&mut self, key: &Q) -> Option<K>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized;
+
+    /// Inserts `key` below the last node of `path`, where `path` is what
+    /// `search_path` returned for this (missing) key. An empty path means the
+    /// tree is empty.
+    fn insert_at(&mut self, path: Vec<Self::NodeRef>, key: K);
+
+    /// Removes the last node of `path`, where `path` is what `search_path`
+    /// returned for a key the tree holds, and returns the stored key.
+    fn delete_at(&mut self, path: Vec<Self::NodeRef>) -> K;
+
+    /// Writes the `RenderStyle::Sideways` picture of a non-empty tree.
+    fn render_sideways(&self, out: &mut dyn fmt::Write) -> fmt::Result
+    where
+        K: Debug;
+
+    /// Extra label text and Graphviz attributes for `node` in `to_dot`.
+    fn dot_node_style(&self, _node: &Self::NodeRef) -> DotNodeStyle {
+        DotNodeStyle::default()
+    }
+
+    /// Text shown for `node` by the top-down and Unicode render styles.
+    fn render_label(&self, node: &Self::NodeRef) -> String
+    where
+        K: Debug,
+    {
+        self.with_key(node, |key| format!("{:?}", key))
+    }
+
+    /// Draws the tree into `out`. An empty tree writes nothing.
+    fn render(&self, out: &mut impl fmt::Write, style: RenderStyle) -> fmt::Result
+    where
+        Self: Sized,
+        K: Debug,
+    {
+        if self.is_empty() {
+            return Ok(());
+        }
+        match style {
+            RenderStyle::Sideways => self.render_sideways(out),
+            RenderStyle::TopDown => render::write_top_down(self, out),
+            RenderStyle::Unicode => render::write_unicode(self, out),
         }
     }
 
-    fn get_min(&self) -> Option<i64> {
-        match &self.get_root() {
-            None => None,
-            Some(node) => Some(node.borrow().get_min()),
+    fn print_tree(&self)
+    where
+        Self: Sized,
+        K: Debug,
+    {
+        if self.is_empty() {
+            println!("This tree is empty!");
+            return;
         }
+        let mut out = String::new();
+        self.render(&mut out, RenderStyle::Sideways).unwrap();
+        print!("{}", out);
     }
 
-    fn get_max(&self) -> Option<i64> {
-        match &self.get_root() {
-            None => None,
-            Some(node) => Some(node.borrow().get_max()),
+    fn get_key(&self, node: &Self::NodeRef) -> K {
+        self.with_key(node, K::clone)
+    }
+
+    fn get_height(&self) -> u32 {
+        self.get_root().map_or(0, |node| self.get_node_height(&node))
+    }
+
+    fn get_min(&self) -> Option<K> {
+        let mut node = self.get_root()?;
+        while let Some(left) = self.get_left(&node) {
+            node = left;
         }
+        Some(self.get_key(&node))
     }
 
-    fn count_leaves(&self) -> u32 {
-        match &self.get_root() {
-            None => 0,
-            Some(node) => node.borrow().count_leaves(),
+    fn get_max(&self) -> Option<K> {
+        let mut node = self.get_root()?;
+        while let Some(right) = self.get_right(&node) {
+            node = right;
         }
+        Some(self.get_key(&node))
+    }
+
+    fn count_leaves(&self) -> u32 {
+        self.get_root().map_or(0, |node| self.count_node_leaves(&node))
     }
 
     fn count_nodes(&self) -> u32 {
-        match &self.get_root() {
-            None => 0,
-            Some(node) => node.borrow().count_nodes(),
+        self.len() as u32
+    }
+
+    fn contain<Q>(&self, key: &Q) -> bool
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key).0
+    }
+
+    /// Number of keys in the tree, in O(1).
+    fn len(&self) -> usize {
+        self.get_root().map_or(0, |node| self.get_node_size(&node))
+    }
+
+    /// The `index`-th smallest key, counting from zero.
+    fn select(&self, mut index: usize) -> Option<K> {
+        let mut current = self.get_root();
+
+        while let Some(node) = current {
+            let left = self.get_left(&node);
+            let left_size = left.as_ref().map_or(0, |left| self.get_node_size(left));
+
+            current = match index.cmp(&left_size) {
+                Ordering::Equal => return Some(self.get_key(&node)),
+                Ordering::Less => left,
+                Ordering::Greater => {
+                    index -= left_size + 1;
+                    self.get_right(&node)
+                }
+            };
         }
+
+        None
     }
 
-    fn contain(&self, key: i64) -> bool {
-        match &self.get_root() {
-            None => false,
-            Some(node) => node.borrow().contains(key),
+    /// Number of keys strictly less than `key`.
+    fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut rank = 0;
+        let mut current = self.get_root();
+
+        while let Some(node) = current {
+            let left = self.get_left(&node);
+            let left_size = left.as_ref().map_or(0, |left| self.get_node_size(left));
+
+            current = match self.with_key(&node, |node_key| node_key.borrow().cmp(key)) {
+                Ordering::Equal => return rank + left_size,
+                Ordering::Greater => left,
+                Ordering::Less => {
+                    rank += left_size + 1;
+                    self.get_right(&node)
+                }
+            };
         }
+
+        rank
     }
 
     fn is_empty(&self) -> bool {
-        match &self.get_root() {
-            Some(_) => false,
-            None => true,
-        }
+        self.get_root().is_none()
     }
 
-    fn search(&self, key: i64) -> (bool, Option<Rc<RefCell<TN>>>) {
+    fn search<Q>(&self, key: &Q) -> (bool, Option<Self::NodeRef>)
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
         let mut parent = None;
-        let mut current = self.get_root().clone();
-    
+        let mut current = self.get_root();
+
         while let Some(node) = current {
-            let node_key = node.borrow().get_key();
-    
-            if node_key == key {
-                return (true, Some(node.clone()));
-            } else if node_key > key {
-                parent = Some(node.clone());
-                current = node.borrow().get_left().clone();
+            let ordering = self.with_key(&node, |node_key| node_key.borrow().cmp(key));
+
+            if ordering == Ordering::Equal {
+                return (true, Some(node));
+            } else if ordering == Ordering::Greater {
+                current = self.get_left(&node);
             } else {
-                parent = Some(node.clone());
-                current = node.borrow().get_right().clone();
+                current = self.get_right(&node);
             }
+            parent = Some(node);
         }
-    
+
         (false, parent)
     }
 
-    fn in_order_traversal(&self) -> Vec<i64> {
-        let mut result = Vec::new();
-        if self.get_root().is_none() {
-            return result;
+    fn search_path<Q>(&self, key: &Q) -> (bool, Vec<Self::NodeRef>)
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut path = Vec::new();
+        let mut current = self.get_root();
+
+        while let Some(node) = current {
+            let ordering = self.with_key(&node, |node_key| node_key.borrow().cmp(key));
+
+            current = match ordering {
+                Ordering::Equal => {
+                    path.push(node);
+                    return (true, path);
+                }
+                Ordering::Greater => self.get_left(&node),
+                Ordering::Less => self.get_right(&node),
+            };
+            path.push(node);
         }
-    
-        let mut stack = Vec::new();
-        let mut node = self.get_root().clone();
-    
-        while !stack.is_empty() || node.is_some() {
-            while let Some(current) = node {
-                stack.push(current.clone());
-                node = current.borrow().get_left().clone();
-            }
-    
-            if let Some(current) = stack.pop() {
-                result.push(current.borrow().get_key());
-                node = current.borrow().get_right().clone();
-            }
+
+        (false, path)
+    }
+
+    fn iter(&self) -> Iter<'_, K, Self>
+    where
+        Self: Sized,
+    {
+        Iter::new(self)
+    }
+
+    fn iter_pre_order(&self) -> PreOrderIter<'_, K, Self>
+    where
+        Self: Sized,
+    {
+        PreOrderIter::new(self)
+    }
+
+    fn iter_post_order(&self) -> PostOrderIter<'_, K, Self>
+    where
+        Self: Sized,
+    {
+        PostOrderIter::new(self)
+    }
+
+    fn iter_level_order(&self) -> LevelOrderIter<'_, K, Self>
+    where
+        Self: Sized,
+    {
+        LevelOrderIter::new(self)
+    }
+
+    /// Greatest key less than or equal to `key`.
+    fn floor<Q>(&self, key: &Q) -> Option<K>
+    where
+        Self: Sized,
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        closest(self, key, Ordering::Less, true)
+    }
+
+    /// Smallest key greater than or equal to `key`.
+    fn ceiling<Q>(&self, key: &Q) -> Option<K>
+    where
+        Self: Sized,
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        closest(self, key, Ordering::Greater, true)
+    }
+
+    /// Greatest key strictly less than `key`.
+    fn predecessor<Q>(&self, key: &Q) -> Option<K>
+    where
+        Self: Sized,
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        closest(self, key, Ordering::Less, false)
+    }
+
+    /// Smallest key strictly greater than `key`.
+    fn successor<Q>(&self, key: &Q) -> Option<K>
+    where
+        Self: Sized,
+        K: std::borrow::Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        closest(self, key, Ordering::Greater, false)
+    }
+
+    /// Keys inside `range` in ascending order, in O(log n + k).
+    fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, Self, R>
+    where
+        Self: Sized,
+    {
+        Range::new(self, range)
+    }
+
+    /// Keys in `self` or `other`, ascending and without repeats.
+    fn union<'a>(&'a self, other: &'a Self) -> Union<'a, K, Self>
+    where
+        Self: Sized,
+    {
+        Union::new(self, other)
+    }
+
+    /// Keys in both `self` and `other`, ascending.
+    fn intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, K, Self>
+    where
+        Self: Sized,
+    {
+        Intersection::new(self, other)
+    }
+
+    /// Keys in `self` but not in `other`, ascending.
+    fn difference<'a>(&'a self, other: &'a Self) -> Difference<'a, K, Self>
+    where
+        Self: Sized,
+    {
+        Difference::new(self, other)
+    }
+
+    /// Keys in exactly one of `self` and `other`, ascending.
+    fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SymmetricDifference<'a, K, Self>
+    where
+        Self: Sized,
+    {
+        SymmetricDifference::new(self, other)
+    }
+
+    fn is_subset(&self, other: &Self) -> bool
+    where
+        Self: Sized,
+    {
+        self.len() <= other.len() && self.difference(other).next().is_none()
+    }
+
+    fn is_superset(&self, other: &Self) -> bool
+    where
+        Self: Sized,
+    {
+        other.is_subset(self)
+    }
+
+    fn is_disjoint(&self, other: &Self) -> bool
+    where
+        Self: Sized,
+    {
+        self.intersection(other).next().is_none()
+    }
+
+    /// The tree in Graphviz DOT format.
+    fn to_dot(&self) -> String
+    where
+        Self: Sized,
+        K: Debug,
+    {
+        self.to_dot_with(&DotOptions::default())
+    }
+
+    fn to_dot_with(&self, options: &DotOptions) -> String
+    where
+        Self: Sized,
+        K: Debug,
+    {
+        dot::write_dot(self, options)
+    }
+
+    fn in_order_traversal(&self) -> Vec<K>
+    where
+        Self: Sized,
+    {
+        self.iter().collect()
+    }
+
+    fn pre_order_traversal(&self) -> Vec<K>
+    where
+        Self: Sized,
+    {
+        self.iter_pre_order().collect()
+    }
+
+    fn post_order_traversal(&self) -> Vec<K>
+    where
+        Self: Sized,
+    {
+        self.iter_post_order().collect()
+    }
+}
+
+/// Collects keys given in ascending order, keeping repeated keys once.
+/// Panics, naming `caller`, if a key is smaller than the one before it.
+pub(crate) fn collect_sorted<K: Ord>(keys: impl IntoIterator<Item = K>, caller: &str) -> Vec<K> {
+    let mut sorted: Vec<K> = Vec::new();
+    for key in keys {
+        match sorted.last().map(|last| last.cmp(&key)) {
+            Some(Ordering::Greater) => panic!("{caller}: keys are not in ascending order"),
+            Some(Ordering::Equal) => {}
+            _ => sorted.push(key),
         }
-    
-        result
-    }
-
-    fn pre_order_traversal(&self) -> Vec<i64> {
-        let result = Vec::new();
-        if self.get_root().is_none() {
-            return result;
-        }
-    
-        let mut result = Vec::new();
-        let root = self.get_root().clone();
-        if let Some(node) = root {
-            let mut stack = vec![node.clone()];
-    
-            while let Some(curr) = stack.pop() {
-                result.push(curr.borrow().get_key());
-                
-                if let Some(right) = curr.borrow().get_right() {
-                    stack.push(right.clone());
-                }
-    
-                if let Some(left) = curr.borrow().get_left() {
-                    stack.push(left.clone());
-                }
-            }
+    }
+    sorted
+}
+
+/// Merges two ascending key lists into one, keeping the key from `first`
+/// when both hold it.
+pub(crate) fn merge_sorted<K: Ord>(first: Vec<K>, second: Vec<K>) -> Vec<K> {
+    let mut merged = Vec::with_capacity(first.len() + second.len());
+    let mut second = second.into_iter().peekable();
+    for key in first {
+        while let Some(other) = second.next_if(|other| *other < key) {
+            merged.push(other);
+        }
+        second.next_if(|other| *other == key);
+        merged.push(key);
+    }
+    merged.extend(second);
+    merged
+}
+
+/// Walks from the root towards `key`, remembering the last node on the `side`
+/// of it. An exact match is returned straight away when `inclusive` is set.
+fn closest<K, T, Q>(tree: &T, key: &Q, side: Ordering, inclusive: bool) -> Option<K>
+where
+    K: Ord + Clone + std::borrow::Borrow<Q>,
+    T: Tree<K>,
+    Q: Ord + ?Sized,
+{
+    let mut best = None;
+    let mut current = tree.get_root();
+
+    while let Some(node) = current {
+        let ordering = tree.with_key(&node, |node_key| node_key.borrow().cmp(key));
+
+        if ordering == Ordering::Equal && inclusive {
+            return Some(tree.get_key(&node));
+        }
+        if ordering == side {
+            best = Some(tree.get_key(&node));
+        }
+        let go_right = ordering == Ordering::Less
+            || (ordering == Ordering::Equal && side == Ordering::Greater);
+        current = if go_right {
+            tree.get_right(&node)
+        } else {
+            tree.get_left(&node)
+        };
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use proptest::prelude::*;
+
+    use crate::arenaavltree::ArenaAVLTree;
+    use crate::arenarbtree::ArenaRBTree;
+    use crate::avltree::AVLTree;
+    use crate::invariant::InvariantViolation;
+    use crate::persistentrbtree::PersistentRBTree;
+    use crate::rbtree::RBTree;
+    use crate::tree::Tree;
+
+    fn check_neighbours<T: Tree<i64>>(mut tree: T) {
+        assert_eq!(tree.floor(&5), None);
+        assert_eq!(tree.successor(&5), None);
+
+        for number in (10..=100).step_by(10) {
+            tree.insert(number);
+        }
+
+        assert_eq!(tree.floor(&35), Some(30));
+        assert_eq!(tree.floor(&30), Some(30));
+        assert_eq!(tree.floor(&5), None);
+        assert_eq!(tree.ceiling(&35), Some(40));
+        assert_eq!(tree.ceiling(&40), Some(40));
+        assert_eq!(tree.ceiling(&105), None);
+        assert_eq!(tree.predecessor(&30), Some(20));
+        assert_eq!(tree.predecessor(&31), Some(30));
+        assert_eq!(tree.predecessor(&10), None);
+        assert_eq!(tree.successor(&30), Some(40));
+        assert_eq!(tree.successor(&29), Some(30));
+        assert_eq!(tree.successor(&100), None);
+
+        for number in (10..=100).step_by(10) {
+            assert_eq!(tree.predecessor(&number), if number > 10 { Some(number - 10) } else { None });
+            assert_eq!(tree.successor(&number), if number < 100 { Some(number + 10) } else { None });
         }
-        result
     }
 
-    fn post_order_traversal(&self) -> Vec<i64> {
-        let result = Vec::new();
-        if self.get_root().is_none() {
-            return result;
+    fn check_order_statistics<T: Tree<i64>>(mut tree: T) {
+        assert_eq!(tree.len(), 0);
+        assert_eq!(tree.select(0), None);
+        assert_eq!(tree.rank(&10), 0);
+
+        let input = vec![9, 5, 15, 11, 19, 10, 14, 20, 13, 12, 18, 25, 6, 16, 4, 3, 2, 1, 7, 8];
+        let to_delete = vec![9, 20, 13, 6, 11, 15, 1, 2, 3, 25, 14];
+        for number in &input {
+            tree.insert(*number);
+        }
+        tree.insert(9);
+        assert_eq!(tree.len(), input.len());
+        for number in &to_delete {
+            tree.delete(number);
         }
-    
-        let mut result = Vec::new();
-        let root = self.get_root().clone();
+        tree.delete(&100);
+
+        let remaining = tree.in_order_traversal();
+        assert_eq!(tree.len(), remaining.len());
+        assert_eq!(tree.len() as u32, tree.count_nodes());
+        for (index, number) in remaining.iter().enumerate() {
+            assert_eq!(tree.select(index), Some(*number));
+            assert_eq!(tree.rank(number), index);
+            assert_eq!(tree.rank(&(number + 1)), index + 1);
+        }
+        assert_eq!(tree.select(remaining.len()), None);
+        assert_eq!(tree.rank(&0), 0);
+    }
+
+    #[test]
+    fn test_order_statistics() {
+        check_order_statistics(RBTree::new());
+        check_order_statistics(AVLTree::new());
+    }
+
+    #[test]
+    fn test_neighbours() {
+        check_neighbours(RBTree::new());
+        check_neighbours(AVLTree::new());
+    }
 
-        if let Some(node) = root {
-            let mut stack = vec![node.clone()];
-    
-            while let Some(curr) = stack.pop() {
-                result.insert(0, curr.borrow().get_key());
-                
-                if let Some(left) = curr.borrow().get_left() {
-                    stack.push(left.clone());
+    #[derive(Clone, Debug)]
+    enum Op {
+        Insert(i64),
+        Delete(i64),
+        Contain(i64),
+        DeleteAt(i64),
+    }
+
+    /// Keys come from a small range so deletes and lookups often hit.
+    fn op_strategy() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            3 => (0..64i64).prop_map(Op::Insert),
+            2 => (0..64i64).prop_map(Op::Delete),
+            1 => (0..64i64).prop_map(Op::Contain),
+            1 => (0..64i64).prop_map(Op::DeleteAt),
+        ]
+    }
+
+    /// Replays `ops` on `T` and on a `BTreeSet`, comparing the two and
+    /// validating the tree after every step.
+    fn check_against_model<T: Tree<i64>>(
+        ops: &[Op],
+        validate: fn(&T) -> Result<(), InvariantViolation<i64>>,
+    ) -> Result<(), TestCaseError> {
+        let mut tree = T::new();
+        let mut model = BTreeSet::new();
+
+        for (step, op) in ops.iter().enumerate() {
+            match *op {
+                Op::Insert(key) => {
+                    prop_assert_eq!(tree.insert(key), model.insert(key), "step {}", step);
                 }
-    
-                if let Some(right) = curr.borrow().get_right() {
-                    stack.push(right.clone());
+                Op::Delete(key) => {
+                    prop_assert_eq!(tree.delete(&key), model.take(&key), "step {}", step);
                 }
+                Op::Contain(key) => {
+                    prop_assert_eq!(tree.contain(&key), model.contains(&key), "step {}", step);
+                }
+                Op::DeleteAt(key) => {
+                    let (exists, path) = tree.search_path(&key);
+                    let removed = exists.then(|| tree.delete_at(path));
+                    prop_assert_eq!(removed, model.take(&key), "step {}", step);
+                }
+            }
+            if let Err(violation) = validate(&tree) {
+                return Err(TestCaseError::fail(format!("step {}: {}", step, violation)));
             }
+            prop_assert_eq!(tree.len(), model.len(), "step {}", step);
+            prop_assert_eq!(tree.get_min(), model.first().copied(), "step {}", step);
+            prop_assert_eq!(tree.get_max(), model.last().copied(), "step {}", step);
+            prop_assert_eq!(tree.in_order_traversal(), model.iter().copied().collect::<Vec<_>>(), "step {}", step);
+        }
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn test_rbtree_matches_btreeset(ops in prop::collection::vec(op_strategy(), 0..300)) {
+            check_against_model::<RBTree<i64>>(&ops, RBTree::validate)?;
+        }
+
+        #[test]
+        fn test_avltree_matches_btreeset(ops in prop::collection::vec(op_strategy(), 0..300)) {
+            check_against_model::<AVLTree<i64>>(&ops, AVLTree::validate)?;
+        }
+
+        #[test]
+        fn test_arena_rbtree_matches_btreeset(ops in prop::collection::vec(op_strategy(), 0..300)) {
+            check_against_model::<ArenaRBTree<i64>>(&ops, ArenaRBTree::validate)?;
+        }
+
+        #[test]
+        fn test_arena_avltree_matches_btreeset(ops in prop::collection::vec(op_strategy(), 0..300)) {
+            check_against_model::<ArenaAVLTree<i64>>(&ops, ArenaAVLTree::validate)?;
+        }
+
+        #[test]
+        fn test_persistent_rbtree_matches_btreeset(ops in prop::collection::vec(op_strategy(), 0..300)) {
+            check_against_model::<PersistentRBTree<i64>>(&ops, PersistentRBTree::validate)?;
         }
-        result
     }
 }
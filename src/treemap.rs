@@ -0,0 +1,358 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+
+use crate::avltree::AVLTree;
+use crate::rbtree::RBTree;
+use crate::tree::Tree;
+
+/// Key stored in the tree behind a `TreeMap`: the user key together with the
+/// slot holding its value. Only `key` takes part in comparisons, so the slot
+/// travels with the key through rotations and deletions.
+#[derive(Clone)]
+pub struct MapKey<K> {
+    pub key: K,
+    slot: usize,
+}
+
+impl<K: Ord> PartialEq for MapKey<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<K: Ord> Eq for MapKey<K> {}
+
+impl<K: Ord> PartialOrd for MapKey<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord> Ord for MapKey<K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+impl<K> std::borrow::Borrow<K> for MapKey<K> {
+    fn borrow(&self) -> &K {
+        &self.key
+    }
+}
+
+impl<K: Debug> Debug for MapKey<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.key.fmt(f)
+    }
+}
+
+pub type RBTreeMap<K, V> = TreeMap<K, V, RBTree<MapKey<K>>>;
+pub type AVLTreeMap<K, V> = TreeMap<K, V, AVLTree<MapKey<K>>>;
+
+/// Ordered map balanced by one of the set trees. Values live in `values`,
+/// indexed by the slot stored next to each key, so they can be borrowed
+/// without holding a `RefCell` guard on the node.
+pub struct TreeMap<K, V, T> {
+    tree: T,
+    values: Vec<Option<V>>,
+    free_slots: Vec<usize>,
+    _marker: PhantomData<K>,
+}
+
+impl<K, V, T> TreeMap<K, V, T>
+where
+    K: Ord + Clone + Debug,
+    T: Tree<MapKey<K>>,
+{
+    pub fn new() -> Self {
+        TreeMap {
+            tree: T::new(),
+            values: Vec::new(),
+            free_slots: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.tree.contain(key)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let slot = self._find_slot(key)?;
+        self.values[slot].as_ref()
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let slot = self._find_slot(key)?;
+        self.values[slot].as_mut()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (exists, path) = self.tree.search_path(&key);
+        if exists {
+            let slot = self.tree.with_key(path.last().unwrap(), |map_key| map_key.slot);
+            return self.values[slot].replace(value);
+        }
+        let slot = self._allocate_slot(value);
+        self.tree.insert_at(path, MapKey { key, slot });
+        None
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let slot = self.tree.delete(key)?.slot;
+        self._free_slot(slot)
+    }
+
+    /// Looks `key` up once; a vacant entry keeps the search path so inserting
+    /// through it does not walk the tree again.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, T> {
+        let (exists, path) = self.tree.search_path(&key);
+        if exists {
+            let slot = self.tree.with_key(path.last().unwrap(), |map_key| map_key.slot);
+            Entry::Occupied(OccupiedEntry { map: self, key, slot, path })
+        } else {
+            Entry::Vacant(VacantEntry { map: self, key, path })
+        }
+    }
+
+    fn _find_slot(&self, key: &K) -> Option<usize> {
+        match self.tree.search(key) {
+            (true, Some(node)) => Some(self.tree.with_key(&node, |map_key| map_key.slot)),
+            _ => None,
+        }
+    }
+
+    fn _allocate_slot(&mut self, value: V) -> usize {
+        match self.free_slots.pop() {
+            Some(slot) => {
+                self.values[slot] = Some(value);
+                slot
+            }
+            None => {
+                self.values.push(Some(value));
+                self.values.len() - 1
+            }
+        }
+    }
+
+    fn _free_slot(&mut self, slot: usize) -> Option<V> {
+        self.free_slots.push(slot);
+        self.values[slot].take()
+    }
+}
+
+impl<K, V, T> Default for TreeMap<K, V, T>
+where
+    K: Ord + Clone + Debug,
+    T: Tree<MapKey<K>>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub enum Entry<'a, K, V, T>
+where
+    K: Ord + Clone,
+    T: Tree<MapKey<K>>,
+{
+    Vacant(VacantEntry<'a, K, V, T>),
+    Occupied(OccupiedEntry<'a, K, V, T>),
+}
+
+pub struct VacantEntry<'a, K, V, T>
+where
+    K: Ord + Clone,
+    T: Tree<MapKey<K>>,
+{
+    map: &'a mut TreeMap<K, V, T>,
+    key: K,
+    path: Vec<T::NodeRef>,
+}
+
+pub struct OccupiedEntry<'a, K, V, T>
+where
+    K: Ord + Clone,
+    T: Tree<MapKey<K>>,
+{
+    map: &'a mut TreeMap<K, V, T>,
+    key: K,
+    slot: usize,
+    path: Vec<T::NodeRef>,
+}
+
+impl<'a, K, V, T> Entry<'a, K, V, T>
+where
+    K: Ord + Clone + Debug,
+    T: Tree<MapKey<K>>,
+{
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Vacant(entry) => entry.key(),
+            Entry::Occupied(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Vacant(entry) => entry.insert(default),
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Vacant(entry) => entry.insert(default()),
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+        }
+    }
+}
+
+impl<'a, K, V, T> VacantEntry<'a, K, V, T>
+where
+    K: Ord + Clone + Debug,
+    T: Tree<MapKey<K>>,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        let slot = self.map._allocate_slot(value);
+        self.map.tree.insert_at(self.path, MapKey { key: self.key, slot });
+        self.map.values[slot].as_mut().unwrap()
+    }
+}
+
+impl<'a, K, V, T> OccupiedEntry<'a, K, V, T>
+where
+    K: Ord + Clone + Debug,
+    T: Tree<MapKey<K>>,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn get(&self) -> &V {
+        self.map.values[self.slot].as_ref().unwrap()
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.map.values[self.slot].as_mut().unwrap()
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.map.values[self.slot].as_mut().unwrap()
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry through the path found by `entry`, without
+    /// searching again.
+    pub fn remove(self) -> V {
+        self.map.tree.delete_at(self.path);
+        self.map._free_slot(self.slot).unwrap()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::tree::Tree;
+    use crate::treemap::{AVLTreeMap, Entry, MapKey, RBTreeMap, TreeMap};
+
+    fn check_map<T: Tree<MapKey<i64>>>(mut map: TreeMap<i64, String, T>) {
+        let input = vec![9, 5, 15, 11, 19, 10, 14, 20, 13, 12, 18, 25, 6, 16, 4, 3, 2, 1, 7, 8];
+        let to_delete = vec![9, 20, 13, 6, 11, 15, 1, 2, 3, 25, 14];
+
+        assert!(map.is_empty());
+        for number in &input {
+            assert_eq!(map.insert(*number, format!("v{}", number)), None);
+        }
+        assert_eq!(map.len(), input.len());
+        assert_eq!(map.insert(5, "five".to_string()), Some("v5".to_string()));
+        assert_eq!(map.get(&5).unwrap(), "five");
+
+        map.get_mut(&7).unwrap().push('!');
+        assert_eq!(map.get(&7).unwrap(), "v7!");
+
+        for number in &to_delete {
+            assert_eq!(map.remove(number), Some(format!("v{}", number)));
+            assert_eq!(map.remove(number), None);
+        }
+        assert_eq!(map.len(), input.len() - to_delete.len());
+        for number in &input {
+            assert_eq!(map.contains_key(number), !to_delete.contains(number));
+        }
+        assert_eq!(map.get(&18).unwrap(), "v18");
+
+        // Freed slots are reused for new values.
+        assert_eq!(map.insert(100, "v100".to_string()), None);
+        assert_eq!(map.get(&100).unwrap(), "v100");
+        assert_eq!(map.len(), input.len() - to_delete.len() + 1);
+    }
+
+    fn check_entry<T: Tree<MapKey<String>>>(mut map: TreeMap<String, u32, T>) {
+        let text = "the quick brown fox jumps over the lazy dog the end";
+        for word in text.split_whitespace() {
+            map.entry(word.to_string()).and_modify(|count| *count += 1).or_insert(1);
+        }
+
+        assert_eq!(map.len(), 9);
+        assert_eq!(map.get(&"the".to_string()), Some(&3));
+        assert_eq!(map.get(&"fox".to_string()), Some(&1));
+        assert_eq!(*map.entry("cat".to_string()).or_default(), 0);
+        assert_eq!(*map.entry("cat".to_string()).or_insert_with(|| 7), 0);
+
+        match map.entry("lazy".to_string()) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), 1),
+            Entry::Vacant(_) => panic!("lazy should be present"),
+        }
+        assert!(!map.contains_key(&"lazy".to_string()));
+        assert_eq!(map.len(), 9);
+    }
+
+    #[test]
+    fn test_rbtreemap() {
+        check_map(RBTreeMap::new());
+        check_entry(RBTreeMap::new());
+    }
+
+    #[test]
+    fn test_avltreemap() {
+        check_map(AVLTreeMap::new());
+        check_entry(AVLTreeMap::new());
+    }
+}
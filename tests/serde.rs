@@ -0,0 +1,72 @@
+#![cfg(feature = "serde")]
+
+use serde::{Deserialize, Serialize};
+
+use tree::avltree::AVLTree;
+use tree::rbtree::RBTree;
+use tree::tree::Tree;
+
+#[derive(Serialize, Deserialize)]
+struct Index {
+    sorted: RBTree<i64>,
+    #[serde(with = "tree::serialize::exact")]
+    exact: RBTree<i64>,
+    #[serde(with = "tree::serialize::exact")]
+    balanced: AVLTree<i64>,
+}
+
+#[test]
+fn test_serde_round_trip() {
+    let mut index = Index {
+        sorted: RBTree::new(),
+        exact: RBTree::new(),
+        balanced: AVLTree::new(),
+    };
+    for number in [5, 3, 8, 1] {
+        index.sorted.insert(number);
+        index.exact.insert(number);
+        index.balanced.insert(number);
+    }
+    let json = serde_json::to_string(&index).unwrap();
+    assert!(json.starts_with("{\"sorted\":[1,3,5,8],\"exact\":{\"key\":5,\"color\":\"Black\","));
+
+    let loaded: Index = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.sorted.in_order_traversal(), vec![1, 3, 5, 8]);
+    assert_eq!(loaded.exact.pre_order_traversal(), index.exact.pre_order_traversal());
+    assert_eq!(loaded.exact.to_dot(), index.exact.to_dot());
+    assert_eq!(loaded.balanced.to_string(), index.balanced.to_string());
+    loaded.exact.validate().unwrap();
+
+    let red_root = json.replacen("\"color\":\"Black\"", "\"color\":\"Red\"", 1);
+    let error = serde_json::from_str::<Index>(&red_root).err().unwrap();
+    assert!(error.to_string().starts_with("key 5: root is red"));
+    let unsorted = json.replacen("[1,3,5,8]", "[1,5,3,8]", 1);
+    let error = serde_json::from_str::<Index>(&unsorted).err().unwrap();
+    assert!(error.to_string().starts_with("key 3: keys out of order"));
+    let duplicate = json.replacen("[1,3,5,8]", "[1,3,3,8]", 1);
+    assert!(serde_json::from_str::<Index>(&duplicate).is_err());
+    let wrong_height = json.replacen("\"height\":3", "\"height\":4", 1);
+    let error = serde_json::from_str::<Index>(&wrong_height).err().unwrap();
+    assert!(error.to_string().starts_with("key 5: stored height is wrong"));
+}
+
+/// Borrows its text, so it can be serialized but not deserialized.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+struct Label<'a>(&'a str);
+
+#[derive(Serialize)]
+struct Labels<'a> {
+    #[serde(serialize_with = "tree::serialize::exact::serialize")]
+    exact: AVLTree<Label<'a>>,
+}
+
+#[test]
+fn test_serialize_only_keys() {
+    let text = String::from("b a c");
+    let mut labels = Labels { exact: AVLTree::new() };
+    for word in text.split(' ') {
+        labels.exact.insert(Label(word));
+    }
+    let json = serde_json::to_string(&labels).unwrap();
+    assert!(json.starts_with("{\"exact\":{\"key\":\"b\",\"height\":2,"));
+}